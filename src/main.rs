@@ -1,5 +1,6 @@
-use clap::{arg, Arg, Command};
+use clap::{arg, Arg, ArgAction, ArgMatches, Command};
 use std::io;
+use std::process::ExitCode;
 use yy1::*;
 
 mod yy1;
@@ -12,21 +13,55 @@ fn cli() -> Command {
         .arg_required_else_help(true)
         .arg(
             arg!(input: [INPUT])
-                .help("KiCad placement file")
-                .required(true),
+                .help("Placement (KiCad, Altium, Eagle, EasyEDA) or KiCad board file")
+                .required_unless_present("inspect"),
+        )
+        .arg(
+            arg!(output: [OUTPUT])
+                .help("Output file(s)")
+                .required_unless_present("inspect"),
         )
-        .arg(arg!(output: [OUTPUT]).help("Output file(s)").required(true))
         .arg(
             Arg::new("package_map")
                 .long("rename")
                 .short('r')
                 .help("Package rename file"),
         )
+        .arg(
+            Arg::new("units")
+                .long("units")
+                .short('u')
+                .help("Placement file units (mm, in, mil)"),
+        )
+        .arg(
+            Arg::new("column_map")
+                .long("columns")
+                .short('c')
+                .help("Column mapping profile for custom placement files"),
+        )
+        .arg(
+            Arg::new("design_bom")
+                .long("design-bom")
+                .help("Design BOM file (EasyEDA, KiCad CSV or XML)"),
+        )
         .arg(
             Arg::new("feeder_config")
                 .long("feeder")
                 .short('f')
-                .help("Feeder config file"),
+                .help("Feeder config file (tray and strip pockets are listed in OUTPUT_picks.csv)"),
+        )
+        .arg(
+            Arg::new("value_aliases")
+                .long("aliases")
+                .requires("feeder_config")
+                .help("Value alias file for feeder matching"),
+        )
+        .arg(
+            Arg::new("part_library")
+                .long("library")
+                .short('l')
+                .requires("feeder_config")
+                .help("Part library file (by manufacturer part number)"),
         )
         .arg(
             Arg::new("nozzle_config")
@@ -35,6 +70,17 @@ fn cli() -> Command {
                 .requires("feeder_config")
                 .help("Nozzle config file"),
         )
+        .arg(
+            Arg::new("machine_profile")
+                .long("machine")
+                .short('m')
+                .help("Machine profile file"),
+        )
+        .arg(
+            Arg::new("overrides")
+                .long("overrides")
+                .help("Component override file"),
+        )
         .arg(
             Arg::new("offset")
                 .allow_hyphen_values(true)
@@ -43,6 +89,18 @@ fn cli() -> Command {
                 .short('o')
                 .help("PCB offset (x:y)"),
         )
+        .arg(
+            Arg::new("overall_offset")
+                .long("overall-offset")
+                .num_args(0)
+                .help("Write PCB offset into job header instead of component positions"),
+        )
+        .arg(
+            Arg::new("board_width")
+                .long("board-width")
+                .short('w')
+                .help("Board width for bottom side mirroring"),
+        )
         .arg(
             Arg::new("panel")
                 .long("panel")
@@ -69,19 +127,102 @@ fn cli() -> Command {
                 .long("skip")
                 .help("Skip until component number"),
         )
+        .arg(
+            Arg::new("include")
+                .long("include")
+                .value_delimiter(',')
+                .action(ArgAction::Append)
+                .help("Place only matching components ([field=]glob, e.g. U*, package=QFN*)"),
+        )
+        .arg(
+            Arg::new("exclude")
+                .long("exclude")
+                .value_delimiter(',')
+                .action(ArgAction::Append)
+                .help("Exclude matching components ([field=]glob, e.g. J*, TP*, value=DNP)"),
+        )
+        .arg(
+            Arg::new("variant")
+                .long("variant")
+                .help("Assembly variant (matched against the Variant or Config field)"),
+        )
         .arg(
             Arg::new("fiducial")
                 .allow_hyphen_values(true)
+                .value_delimiter(',')
                 .long("fiducial")
-                .help("Fiducial designator or position"),
+                .help("Fiducial designators or positions (up to two, auto-selected if omitted)"),
+        )
+        .arg(
+            Arg::new("measured_fiducial")
+                .allow_hyphen_values(true)
+                .value_delimiter(',')
+                .long("measured")
+                .requires("fiducial")
+                .help("Measured fiducial positions for software correction (x:y)"),
+        )
+        .arg(
+            Arg::new("learn")
+                .long("learn")
+                .conflicts_with_all(["feeder_config", "panel", "offset", "bom"])
+                .help("Learn overrides from machine edited job (INPUT: generated job, OUTPUT: override file)"),
+        )
+        .arg(
+            Arg::new("inspect")
+                .long("inspect")
+                .conflicts_with_all([
+                    "input",
+                    "output",
+                    "package_map",
+                    "units",
+                    "column_map",
+                    "design_bom",
+                    "feeder_config",
+                    "value_aliases",
+                    "part_library",
+                    "nozzle_config",
+                    "machine_profile",
+                    "overrides",
+                    "offset",
+                    "overall_offset",
+                    "board_width",
+                    "panel",
+                    "explode",
+                    "bom",
+                    "skip",
+                    "include",
+                    "exclude",
+                    "variant",
+                    "fiducial",
+                    "measured_fiducial",
+                    "learn",
+                ])
+                .help("Validate YY1 job file"),
+        )
+        .arg(
+            Arg::new("strict")
+                .long("strict")
+                .action(ArgAction::SetTrue)
+                .help("Treat warnings as errors"),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .value_parser(["text", "json"])
+                .default_value("text")
+                .help("Diagnostics format"),
         )
 }
 
+fn parse_length(val: &str) -> Option<f32> {
+    Units::Millimeters.parse_length(val)
+}
+
 fn parse_offset(offset: &str) -> Result<Position, String> {
     let (x, y) = offset.split_once(':').ok_or("Invalid offset config")?;
     Ok(Position::new(
-        x.parse().map_err(|_| "Invalid X offset")?,
-        y.parse().map_err(|_| "Invalid Y offset")?,
+        parse_length(x).ok_or("Invalid X offset")?,
+        parse_length(y).ok_or("Invalid Y offset")?,
     ))
 }
 
@@ -91,8 +232,8 @@ fn parse_fiducial(fudicial: &str) -> Result<Fiducial, String> {
             .split_once(':')
             .ok_or("Invalid fiducial position")?;
         Ok(Fiducial::Position(Position::new(
-            x.parse().map_err(|_| "Invalid fiducial X position")?,
-            y.parse().map_err(|_| "Invalid fiducial Y position")?,
+            parse_length(x).ok_or("Invalid fiducial X position")?,
+            parse_length(y).ok_or("Invalid fiducial Y position")?,
         )))
     } else {
         Ok(Fiducial::Reference(fudicial.into()))
@@ -106,8 +247,8 @@ fn parse_panel_config(panel: &str) -> Result<PanelConfig, String> {
     } else {
         let rows = params[0].parse().map_err(|_| "Invalid panel rows")?;
         let columns = params[1].parse().map_err(|_| "Invalid panel columns")?;
-        let width = params[2].parse().map_err(|_| "Invalid panel unit width")?;
-        let length = params[3].parse().map_err(|_| "Invalid panel unit length")?;
+        let width = parse_length(params[2]).ok_or("Invalid panel unit width")?;
+        let length = parse_length(params[3]).ok_or("Invalid panel unit length")?;
 
         if columns < 2 || rows < 2 {
             Err("Invalid panel config".into())
@@ -122,8 +263,37 @@ fn parse_panel_config(panel: &str) -> Result<PanelConfig, String> {
     }
 }
 
-fn main() -> io::Result<()> {
+fn main() -> ExitCode {
     let matches = cli().get_matches();
+    let format = matches
+        .get_one::<String>("format")
+        .and_then(|val| val.parse::<OutputFormat>().ok())
+        .unwrap_or_default();
+    let mut diagnostics = Diagnostics::new(format, matches.get_flag("strict"));
+    if let Err(err) = run(&matches, &mut diagnostics) {
+        diagnostics.push(err.into());
+    }
+    if let Err(err) = diagnostics.finish() {
+        eprintln!("Error: {err}");
+    }
+    ExitCode::from(diagnostics.exit_code())
+}
+
+fn run(matches: &ArgMatches, diagnostics: &mut Diagnostics) -> io::Result<()> {
+    if let Some(job) = matches.get_one::<String>("inspect") {
+        return inspect(job, diagnostics);
+    }
+
+    if let Some(edited) = matches.get_one::<String>("learn") {
+        return learn(
+            edited,
+            matches.get_one::<String>("input").expect("required"),
+            matches.get_one::<String>("output").expect("required"),
+            matches.get_one::<String>("overrides").map(String::as_str),
+            diagnostics,
+        );
+    }
+
     let offset = matches
         .get_many::<String>("offset")
         .map(|offsets| {
@@ -151,24 +321,81 @@ fn main() -> io::Result<()> {
         .expect("required")
         .to_owned();
     let fiducial = matches
-        .get_one::<String>("fiducial")
-        .map(|fiducial| parse_fiducial(fiducial))
+        .get_many::<String>("fiducial")
+        .map(|fiducials| {
+            fiducials
+                .map(|fiducial| parse_fiducial(fiducial))
+                .collect::<Result<Vec<Fiducial>, String>>()
+        })
         .transpose()
-        .map_err(io::Error::other)?;
+        .map_err(io::Error::other)?
+        .unwrap_or_default();
+    let measured_fiducial = matches
+        .get_many::<String>("measured_fiducial")
+        .map(|positions| {
+            positions
+                .map(|position| parse_offset(position))
+                .collect::<Result<Vec<Position>, String>>()
+        })
+        .transpose()
+        .map_err(io::Error::other)?
+        .unwrap_or_default();
+    if fiducial.len() > 2 || measured_fiducial.len() > 2 {
+        return Err(io::Error::other("Too many fiducials"));
+    }
+    if !measured_fiducial.is_empty() && measured_fiducial.len() != fiducial.len() {
+        return Err(io::Error::other(
+            "Measured fiducials do not match fiducials",
+        ));
+    }
     let skip_until = matches
         .get_one::<String>("skip")
         .map(|val| val.parse())
         .transpose()
         .map_err(io::Error::other)?;
+    let board_width = matches
+        .get_one::<String>("board_width")
+        .map(|val| parse_length(val).ok_or("Invalid board width"))
+        .transpose()
+        .map_err(io::Error::other)?;
+    let input_units = matches
+        .get_one::<String>("units")
+        .map(|val| val.parse())
+        .transpose()
+        .map_err(io::Error::other)?
+        .unwrap_or_default();
     let config = Config::new(input, output)
         .feeder_config_path(matches.get_one::<String>("feeder_config").cloned())
+        .value_aliases_path(matches.get_one::<String>("value_aliases").cloned())
+        .part_library_path(matches.get_one::<String>("part_library").cloned())
         .nozzle_config_path(matches.get_one::<String>("nozzle_config").cloned())
         .package_map_path(matches.get_one::<String>("package_map").cloned())
+        .design_bom_path(matches.get_one::<String>("design_bom").cloned())
+        .overrides_path(matches.get_one::<String>("overrides").cloned())
+        .machine_profile_path(matches.get_one::<String>("machine_profile").cloned())
+        .column_map_path(matches.get_one::<String>("column_map").cloned())
         .fiducial(fiducial)
+        .measured_fiducial(measured_fiducial)
+        .include(
+            matches
+                .get_many::<String>("include")
+                .map(|vals| vals.cloned().collect())
+                .unwrap_or_default(),
+        )
+        .exclude(
+            matches
+                .get_many::<String>("exclude")
+                .map(|vals| vals.cloned().collect())
+                .unwrap_or_default(),
+        )
+        .variant(matches.get_one::<String>("variant").cloned())
         .skip_until(skip_until)
         .panel(panel)
         .bom(matches.get_flag("bom"))
-        .offset(offset);
+        .offset(offset)
+        .overall_offset(matches.get_flag("overall_offset"))
+        .board_width(board_width)
+        .input_units(input_units);
 
-    convert(config)
+    convert(config, diagnostics)
 }
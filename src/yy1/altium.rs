@@ -0,0 +1,103 @@
+use super::input::*;
+use super::*;
+
+pub fn is_header(line: &str) -> bool {
+    let line = line.trim_start_matches('"');
+    line.starts_with("Designator") && line.contains("Center-X")
+}
+
+pub fn read_pick_place(text: &str) -> io::Result<Vec<KiCadRecord>> {
+    let lines: Vec<&str> = text.lines().collect();
+    let header_idx = lines
+        .iter()
+        .position(|line| is_header(line))
+        .ok_or_else(|| invalid_input("Pick Place header not found"))?;
+    let rows = read_rows(&lines[header_idx..], detect_delimiter(lines[header_idx]))?;
+    let ((_, header), rows) = rows
+        .split_first()
+        .ok_or_else(|| invalid_input("Pick Place header not found"))?;
+    let header_line = header_idx + 1;
+
+    let column = |prefix: &str| {
+        header
+            .iter()
+            .position(|name| name.starts_with(prefix))
+            .ok_or_else(|| invalid_input_at(format!("Missing {prefix} column"), header_line))
+    };
+    let units: Units = header[column("Center-X")?]
+        .trim_start_matches("Center-X(")
+        .trim_end_matches(')')
+        .parse()
+        .map_err(|err: String| invalid_input_at(err, header_line))?;
+    let reference = column("Designator")?;
+    let value = column("Comment")?;
+    let layer = column("Layer")?;
+    let package = column("Footprint")?;
+    let position_x = column("Center-X")?;
+    let position_y = column("Center-Y")?;
+    let rotation = column("Rotation")?;
+
+    let mut records = vec![];
+    for (line, row) in rows {
+        let line_no = header_idx + line;
+        if row.iter().all(String::is_empty) {
+            continue;
+        }
+        let field = |idx: usize| {
+            row.get(idx)
+                .ok_or_else(|| invalid_input_at("Missing field", line_no))
+        };
+        let layer_name = field(layer)?;
+        let side = parse_side(layer_name)
+            .ok_or_else(|| invalid_input_at(format!("Invalid layer \"{layer_name}\""), line_no))?;
+        records.push(KiCadRecord {
+            reference: field(reference)?.clone(),
+            value: field(value)?.clone(),
+            package: field(package)?.clone(),
+            position_x: parse_length(field(position_x)?, units, line_no)?,
+            position_y: parse_length(field(position_y)?, units, line_no)?,
+            rotation: parse_number(field(rotation)?, line_no)?,
+            side,
+            ..Default::default()
+        });
+    }
+
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_report() {
+        let text = "Altium Designer Pick and Place Locations
+Units used in this file : mil
+
+\"Designator\",\"Comment\",\"Layer\",\"Footprint\",\"Center-X(mil)\",\"Center-Y(mil)\",\"Rotation\"
+\"R1\",\"10k\",\"TopLayer\",\"0603\",\"1000\",\"500\",\"90\"
+
+\"U1\",\"LM358, SOIC\",\"BottomLayer\",\"SOIC-8\",\"200\",\"100\",\"270\"
+";
+        assert!(!is_header("Designator,Comment,Layer"));
+        let records = read_pick_place(text).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].reference, "R1");
+        assert_eq!(records[0].package, "0603");
+        assert!((records[0].position_x - 25.4).abs() < 1e-4);
+        assert!((records[0].position_y - 12.7).abs() < 1e-4);
+        assert_eq!(records[0].side, Side::Top);
+        assert_eq!(records[1].value, "LM358, SOIC");
+        assert_eq!(records[1].rotation, 270.0);
+        assert_eq!(records[1].side, Side::Bottom);
+    }
+
+    #[test]
+    fn reject_invalid_layer() {
+        let text = "Designator\tComment\tLayer\tFootprint\tCenter-X(mm)\tCenter-Y(mm)\tRotation
+R1\t10k\tMid1\t0603\t1\t2\t0
+";
+        assert!(read_pick_place(text).is_err());
+        assert!(read_pick_place("Designator,Comment\n").is_err());
+    }
+}
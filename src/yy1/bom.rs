@@ -0,0 +1,250 @@
+use super::input::*;
+use super::xml::Element;
+use super::*;
+use std::collections::HashMap;
+
+const PART_FIELDS: [&str; 5] = [
+    "LCSC",
+    "LCSC Part",
+    "LCSC Part #",
+    "JLCPCB Part #",
+    "Supplier Part",
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct DesignBomEntry {
+    value: String,
+    package: String,
+    part: String,
+    dnp: bool,
+    fields: HashMap<String, String>,
+}
+
+impl DesignBomEntry {
+    pub fn apply(&self, record: &mut KiCadRecord) {
+        if record.value.is_empty() {
+            record.value = self.value.clone();
+        }
+        if record.package.is_empty() {
+            record.package = self.package.clone();
+        }
+        if !self.part.is_empty() {
+            record.part = self.part.clone();
+        }
+        record.dnp |= self.dnp;
+        for (name, val) in &self.fields {
+            record.fields.insert(name.clone(), val.clone());
+        }
+    }
+
+    fn finish(mut self) -> Self {
+        if let Some((_, name)) = self.package.split_once(':') {
+            self.package = name.into();
+        }
+        if self.part.is_empty() {
+            self.part = PART_FIELDS
+                .iter()
+                .find_map(|name| self.fields.get(*name).filter(|val| !val.is_empty()))
+                .cloned()
+                .unwrap_or_default();
+        }
+        self.dnp |= ["DNP", "dnp", "Do Not Populate"]
+            .iter()
+            .any(|name| self.fields.get(*name).is_some_and(|val| is_set(val)));
+        self
+    }
+}
+
+fn is_set(val: &str) -> bool {
+    !matches!(
+        val.trim().to_lowercase().as_str(),
+        "" | "0" | "n" | "no" | "false"
+    )
+}
+
+/// Reads an EasyEDA or KiCad BOM (CSV or XML netlist), keyed by designator.
+pub fn read_design_bom(path: &str) -> io::Result<HashMap<String, DesignBomEntry>> {
+    parse_design_bom(&read_text(path)?).map_err(|err| in_file(path, err))
+}
+
+fn parse_design_bom(text: &str) -> io::Result<HashMap<String, DesignBomEntry>> {
+    if text.trim_start().starts_with('<') {
+        return read_netlist(text);
+    }
+
+    let lines: Vec<&str> = text.lines().collect();
+    let reference_names = ["Designator", "Reference", "References", "Refs", "Ref"];
+    let header_idx = lines
+        .iter()
+        .position(|line| {
+            split_header(line)
+                .iter()
+                .any(|name| reference_names.contains(&name.as_str()))
+        })
+        .ok_or_else(|| invalid_input("BOM header not found"))?;
+    let rows = read_rows(&lines[header_idx..], detect_delimiter(lines[header_idx]))?;
+    let ((_, header), rows) = rows
+        .split_first()
+        .ok_or_else(|| invalid_input("BOM header not found"))?;
+
+    let column = |names: &[&str]| {
+        names
+            .iter()
+            .find_map(|name| header.iter().position(|column| column == name))
+    };
+    let reference = column(&reference_names)
+        .ok_or_else(|| invalid_input_at("Missing Designator column", header_idx + 1))?;
+    let value = column(&["Comment", "Value", "Name"]);
+    let package = column(&["Footprint", "Package"]);
+
+    let mut entries = HashMap::new();
+    for (_, row) in rows {
+        let field = |idx: Option<usize>| {
+            idx.and_then(|idx| row.get(idx))
+                .cloned()
+                .unwrap_or_default()
+        };
+        let fields = header
+            .iter()
+            .zip(row)
+            .enumerate()
+            .filter(|(idx, (_, val))| *idx != reference && !val.is_empty())
+            .map(|(_, (name, val))| (name.clone(), val.clone()))
+            .collect();
+        let entry = DesignBomEntry {
+            value: field(value),
+            package: field(package),
+            fields,
+            ..Default::default()
+        }
+        .finish();
+        for reference in field(Some(reference)).split([',', ' ']) {
+            let reference = reference.trim();
+            if !reference.is_empty() {
+                entries.insert(reference.to_string(), entry.clone());
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+fn split_header(line: &str) -> Vec<String> {
+    read_rows(&[line], detect_delimiter(line)).map_or(vec![], |mut rows| rows.remove(0).1)
+}
+
+/// Reads the components of a KiCad XML netlist or BOM export.
+fn read_netlist(text: &str) -> io::Result<HashMap<String, DesignBomEntry>> {
+    let export = Element::parse(text).map_err(|err| invalid_input(err.to_string()))?;
+    if export.name() != "export" {
+        return Err(invalid_input("Not a KiCad netlist"));
+    }
+    let components = export
+        .child("components")
+        .ok_or_else(|| invalid_input("Netlist components not found"))?;
+
+    let mut entries = HashMap::new();
+    for comp in components.children("comp") {
+        let reference = comp
+            .attr("ref")
+            .ok_or_else(|| invalid_input("Missing component reference"))?;
+        let text = |name: &str| comp.child(name).map(|el| el.text().to_string());
+
+        let mut fields: HashMap<String, String> = comp
+            .child("fields")
+            .into_iter()
+            .flat_map(|fields| fields.children("field"))
+            .filter_map(|field| Some((field.attr("name")?.into(), field.text().into())))
+            .collect();
+        let mut dnp = false;
+        for property in comp.children("property") {
+            match (property.attr("name"), property.attr("value")) {
+                (Some("dnp"), None) => dnp = true,
+                (Some("dnp"), Some(value)) => dnp = is_set(value),
+                (Some(name), Some(value)) => {
+                    fields.entry(name.into()).or_insert(value.into());
+                }
+                _ => {}
+            }
+        }
+
+        let entry = DesignBomEntry {
+            value: text("value").unwrap_or_default(),
+            package: text("footprint").unwrap_or_default(),
+            dnp,
+            fields,
+            ..Default::default()
+        }
+        .finish();
+        entries.insert(reference.into(), entry);
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_header_after_preamble() {
+        let text = "Project;Board\nRev;B\n\nComment;Designator;Footprint\n100nF;C3;C0402\n";
+        let entries = parse_design_bom(text).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries["C3"].value, "100nF");
+        assert_eq!(entries["C3"].package, "C0402");
+        assert!(parse_design_bom("Comment,Footprint\n100nF,C0402\n").is_err());
+    }
+
+    #[test]
+    fn split_designators() {
+        let text = "\"Reference\",\"Value\",\"Footprint\"\n\
+                    \"C1,C2\",\"100nF\",\"Capacitor_SMD:C_0402\"\n\
+                    \"R1 R2\",\"10k\",\"R0402\"\n";
+        let entries = parse_design_bom(text).unwrap();
+        let mut references: Vec<&str> = entries.keys().map(String::as_str).collect();
+        references.sort();
+        assert_eq!(references, ["C1", "C2", "R1", "R2"]);
+        assert_eq!(entries["C2"].package, "C_0402");
+        assert!(!entries["C2"].fields.contains_key("Reference"));
+    }
+
+    #[test]
+    fn part_field_fallback() {
+        let text = "Designator,Comment,Supplier Part,LCSC Part #\n\
+                    C1,100nF,S-100N,C1525\n\
+                    C2,10uF,S-10U,\n\
+                    C3,1uF,,\n";
+        let entries = parse_design_bom(text).unwrap();
+        assert_eq!(entries["C1"].part, "C1525");
+        assert_eq!(entries["C2"].part, "S-10U");
+        assert_eq!(entries["C3"].part, "");
+        assert_eq!(entries["C1"].fields["Supplier Part"], "S-100N");
+    }
+
+    #[test]
+    fn dnp_field() {
+        let text = "Designator,Comment,DNP\nC1,100nF,\nC2,100nF,No\nC3,100nF,DNP\nC4,100nF,1\n";
+        let entries = parse_design_bom(text).unwrap();
+        let dnp: Vec<bool> = ["C1", "C2", "C3", "C4"]
+            .iter()
+            .map(|reference| entries[*reference].dnp)
+            .collect();
+        assert_eq!(dnp, [false, false, true, true]);
+    }
+
+    #[test]
+    fn netlist_properties() {
+        let text = r#"<export><components>
+<comp ref="C1"><value>100n</value><footprint>Capacitor_SMD:C_0402</footprint>
+<property name="dnp"/><property name="LCSC" value="C1525"/></comp>
+<comp ref="C2"><value>1u</value><property name="dnp" value="0"/></comp>
+</components></export>"#;
+        let entries = parse_design_bom(text).unwrap();
+        assert!(entries["C1"].dnp);
+        assert_eq!(entries["C1"].package, "C_0402");
+        assert_eq!(entries["C1"].part, "C1525");
+        assert!(!entries["C2"].dnp);
+        assert!(parse_design_bom("<netlist/>").is_err());
+    }
+}
@@ -1,42 +1,88 @@
 use super::*;
+use feeders::FeederTable;
+use filter::ComponentFilter;
+use library::PartLibrary;
+use machine::{HeaderVariant, MachineProfile};
+use manual::ManualSheet;
 use package::PackageConverter;
 use planner::*;
+use pockets::PocketTracker;
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::{self, Write};
 use std::path::Path;
+use values::ValueNormalizer;
 
 pub struct YY1Converter {
-    fiducial: Position,
     config: Config,
+    machine: MachineProfile,
+    overall_offset: Position,
+    measured_side: Option<Side>,
+    pockets: PocketTracker,
+    manual: ManualSheet,
+    bom: Vec<BOMRecord>,
     steps: Vec<PickAndPlaceStep>,
 }
 
 impl YY1Converter {
-    pub fn try_new(config: Config) -> io::Result<Self> {
-        let mut reader = csv::Reader::from_reader(File::open(&config.input_path)?);
-        let kicad_records: Result<Vec<KiCadRecord>, csv::Error> = reader.deserialize().collect();
+    pub fn try_new(config: Config, diagnostics: &mut Diagnostics) -> io::Result<Self> {
+        let placement = input::read_placement(&config)?;
+        let board_width = config.board_width.or(placement.board_width);
 
-        let feeder_config: Option<HashMap<(String, String), FeederConfig>> =
-            if let Some(path) = &config.feeder_config_path {
-                let mut reader = csv::Reader::from_reader(File::open(path)?);
-                let records: Result<Vec<FeederConfig>, csv::Error> = reader.deserialize().collect();
-                let feeder_config = records
-                    .map_err(|err| io::Error::other(err.to_string()))?
-                    .into_iter()
-                    .map(|cfg| ((cfg.value.clone(), cfg.package.clone()), cfg))
-                    .collect();
-                Some(feeder_config)
-            } else {
-                None
+        let machine = match &config.machine_profile_path {
+            Some(path) => MachineProfile::load(path)?,
+            None => MachineProfile::default(),
+        };
+
+        let mut pocket_grids = HashMap::new();
+        let feeder_config: Option<FeederTable> = if let Some(path) = &config.feeder_config_path {
+            let records: Vec<FeederConfig> = input::read_csv(path, "invalid-feeder-config")?
+                .into_iter()
+                .map(|(line, cfg)| FeederConfig { line, ..cfg })
+                .collect();
+            let library = match &config.part_library_path {
+                Some(path) => PartLibrary::load(path)?,
+                None => PartLibrary::default(),
             };
+            let records: Vec<FeederConfig> = records
+                .into_iter()
+                .map(|cfg| library.resolve(cfg, diagnostics))
+                .collect();
+            if let Some(cfg) = records.iter().find(|cfg| cfg.nozzle.is_none()) {
+                return Err(Diagnostic::error(
+                    "feeder-nozzle",
+                    format!(
+                        "Missing nozzle for feeder #{}. Component: {} - {} {}.",
+                        cfg.feeder, cfg.value, cfg.package, cfg.part
+                    ),
+                )
+                .file(path)
+                .line(cfg.line)
+                .into());
+            }
+            machine.validate_feeders(&records, path)?;
+            pocket_grids = records
+                .iter()
+                .filter_map(|cfg| cfg.pockets().map(|grid| (cfg.feeder, grid)))
+                .collect();
+            let normalizer = match &config.value_aliases_path {
+                Some(path) => ValueNormalizer::load(path)?,
+                None => ValueNormalizer::default(),
+            };
+            Some(FeederTable::new(records, normalizer, &library, path)?)
+        } else {
+            None
+        };
 
         let nozzles_config = match &config.nozzle_config_path {
             Some(path) => {
                 let mut reader = csv::Reader::from_reader(File::open(path)?);
                 let records: Result<Vec<NozzleConfig>, csv::Error> = reader.deserialize().collect();
-                records?.into_iter().map(Option::Some).collect()
+                records?
+                    .into_iter()
+                    .map(|cfg| cfg.with_stations(machine.stations()).map(Option::Some))
+                    .collect::<Result<_, _>>()?
             }
             None => vec![None],
         };
@@ -51,111 +97,277 @@ impl YY1Converter {
         };
         let package_converter = PackageConverter::new(package_map);
 
-        let components: Vec<ComponentRecord> = kicad_records
-            .map_err(|err| io::Error::other(err.to_string()))?
+        let overrides = match &config.overrides_path {
+            Some(path) => overrides::read_overrides(path)?,
+            None => HashMap::new(),
+        };
+        if nozzles_config.iter().all(Option::is_none) {
+            let mut ignored: Vec<&ComponentOverride> = overrides
+                .values()
+                .filter(|component_override| component_override.nozzle.is_some())
+                .collect();
+            ignored.sort_by(|a, b| a.reference.cmp(&b.reference));
+            for component_override in ignored {
+                diagnostics.push(
+                    Diagnostic::warning(
+                        "nozzle-override-ignored",
+                        "Nozzle override requires a nozzle config. Ignoring...",
+                    )
+                    .designator(&component_override.reference)
+                    .file(config.overrides_path.as_deref().unwrap_or_default()),
+                );
+            }
+        }
+
+        let filter = ComponentFilter::new(&config.include, &config.exclude, config.variant.clone());
+        let mut manual = ManualSheet::default();
+
+        let mut components: Vec<ComponentRecord> = placement
+            .records
             .into_iter()
             .map(|comp| {
                 let mut comp: ComponentRecord = comp.into();
                 comp.package = package_converter.rename(&comp.package);
+                // Manual sheet lists design coordinates, before mirroring and feeder rotation
+                let mut design = comp.clone();
+
+                // Bottom positions are mirrored here and shifted by the board width below,
+                // once it is known whether the width is needed
+                if comp.side == Side::Bottom {
+                    comp.position_x = -comp.position_x;
+                    comp.rotation = normalize_rotation(180.0 - comp.rotation);
+                }
+
+                let mut reason = None;
+                let fiducial = comp.value == "Fiducial";
+                if fiducial {
+                    comp.skip = 1;
+                }
 
-                if comp.value == "Fiducial" {
+                if let Some(reason) = filter.exclude_reason(&comp) {
                     comp.skip = 1;
+                    if !fiducial {
+                        manual.push(&design, reason);
+                    }
+                    return comp;
                 }
 
-                if let Some(feeder_config) = &feeder_config {
-                    let comp_kind = (comp.value.clone(), comp.package.clone());
-                    if let Some(feeder) = feeder_config.get(&comp_kind) {
+                // Fiducials are never placed, so feeder rules and defaults must not catch them
+                if let Some(feeder_config) = feeder_config.as_ref().filter(|_| !fiducial) {
+                    if let Some(feeder) = feeder_config.get(&comp.value, &comp.package, comp.mpn())
+                    {
                         comp.feeder = feeder.feeder;
                         comp.pick_height = feeder.pick_height;
                         comp.place_height = feeder.place_height;
                         comp.mount_speed = feeder.mount_speed;
                         comp.mode = feeder.mode;
                         comp.skip = feeder.skip;
-                        comp.part = feeder.part.clone();
-                        comp.rotation = match (comp.rotation + feeder.rotation) % 360.0 {
-                            -0.0 => 0.0,
-                            angle if angle <= -180.0 => angle + 360.0,
-                            angle if angle > 180.0 => angle - 360.0,
-                            angle => angle,
-                        };
+                        if comp.part.is_empty() {
+                            comp.part = feeder.part.clone();
+                        }
+                        comp.rotation = normalize_rotation(comp.rotation + feeder.rotation);
+                        let feeder_path = config.feeder_config_path.as_deref().unwrap_or_default();
                         if feeder.skip == 1 {
-                            eprintln!(
-                                "Warning: Feeder #{} is empty. Component: {} - {}. Skipping...",
-                                comp.feeder, comp.value, comp.package
+                            reason = Some(format!("Feeder #{} is empty", comp.feeder));
+                            diagnostics.push(
+                                Diagnostic::warning(
+                                    "feeder-empty",
+                                    format!(
+                                        "Feeder #{} is empty. Component: {} - {}. Skipping...",
+                                        comp.feeder, comp.value, comp.package
+                                    ),
+                                )
+                                .designator(&comp.reference)
+                                .file(feeder_path)
+                                .line(feeder.line),
                             );
                         }
                         if feeder.feeder == 0 {
-                            eprintln!(
-                                "Warning: Unknown feeder #{}. Component: {} - {}.",
-                                comp.feeder, comp.value, comp.package
+                            diagnostics.push(
+                                Diagnostic::warning(
+                                    "unknown-feeder",
+                                    format!(
+                                        "Unknown feeder #{}. Component: {} - {}.",
+                                        comp.feeder, comp.value, comp.package
+                                    ),
+                                )
+                                .designator(&comp.reference)
+                                .file(feeder_path)
+                                .line(feeder.line),
                             );
                         }
+                        let nozzle = overrides
+                            .get(&comp.reference)
+                            .and_then(|component_override| component_override.nozzle)
+                            .or(feeder.nozzle)
+                            .expect("feeder nozzles are checked on load");
                         for nozzle_config in &nozzles_config {
                             if nozzle_config
-                                .map(|cfg| cfg.contains(feeder.nozzle))
+                                .map(|cfg| cfg.contains(nozzle))
                                 .unwrap_or(false)
                             {
-                                comp.nozzle = Some(feeder.nozzle);
+                                comp.nozzle = Some(nozzle);
                                 break;
                             }
                         }
                         if comp.nozzle.is_none() && nozzles_config.iter().any(Option::is_some) {
                             comp.skip = 1;
-                            eprintln!(
-                                "Warning: Nozzle {:?} not found for component: {} {}. Skipping...",
-                                feeder.nozzle, comp.value, comp.package
+                            reason = Some(format!("Nozzle {nozzle:?} not available"));
+                            diagnostics.push(
+                                Diagnostic::warning(
+                                    "nozzle-not-found",
+                                    format!(
+                                        "Nozzle {:?} not found for component: {} {}. Skipping...",
+                                        nozzle, comp.value, comp.package
+                                    ),
+                                )
+                                .designator(&comp.reference),
                             );
                         }
-                    } else if comp.value != "Fiducial" {
+                    } else {
                         comp.skip = 1;
-                        eprintln!(
-                            "Warning: Feeder not found for component: {} - {}. Skipping...",
-                            comp.value, comp.package
+                        reason = Some("Feeder not found".to_string());
+                        diagnostics.push(
+                            Diagnostic::warning(
+                                "feeder-not-found",
+                                format!(
+                                    "Feeder not found for component: {} - {}. Skipping...",
+                                    comp.value, comp.package
+                                ),
+                            )
+                            .designator(&comp.reference),
                         );
                     }
                 }
 
+                if let Some(component_override) = overrides.get(&comp.reference) {
+                    component_override.apply(&mut comp);
+                    if component_override.skip == Some(1) {
+                        reason = Some("Skipped by override".to_string());
+                    }
+                }
+
+                // Fiducials are skipped but there is nothing to assemble by hand
+                if comp.skip == 1 && !fiducial {
+                    design.part = comp.part.clone();
+                    manual.push(&design, reason.unwrap_or_else(|| "Skipped".to_string()));
+                }
+
                 comp
             })
             .collect();
 
-        let fiducial = match &config.fiducial {
-            Some(Fiducial::Position(position)) => position.clone(),
-            Some(Fiducial::Reference(fiducial_ref)) => components
+        // Job sides follow the components that are placed, not DNP or excluded ones
+        let placed = |side: Side| {
+            components
                 .iter()
-                .find(|fid| fid.reference == *fiducial_ref)
-                .map(|fid| {
-                    let panel = if config.panel.explode {
-                        Size::new(
-                            (config.panel.columns - 1) as f32,
-                            (config.panel.rows - 1) as f32,
-                        )
-                    } else {
-                        Size::zero()
-                    };
-                    Position::new(
-                        fid.position_x + panel.width * config.panel.size.width,
-                        fid.position_y + panel.height * config.panel.size.height,
-                    )
-                })
-                .ok_or(io::Error::other("Fiducial not found"))?,
-            None => Position::zero(),
+                .any(|comp| comp.side == side && comp.skip == 0)
+        };
+        let has_top = placed(Side::Top);
+        let has_bottom = placed(Side::Bottom);
+        let board_width = match board_width {
+            Some(width) => width,
+            None if has_bottom => {
+                return Err(io::Error::other(
+                    "Board width is required for bottom side components",
+                ))
+            }
+            None => 0.0,
         };
+        for comp in components.iter_mut() {
+            if comp.side == Side::Bottom {
+                comp.position_x += board_width;
+            }
+        }
 
+        let sides = match (has_top, has_bottom) {
+            (false, true) => vec![Side::Bottom],
+            (true, true) => vec![Side::Top, Side::Bottom],
+            _ => vec![Side::Top],
+        };
+        let measured_side = if config.measured_fiducial.is_empty() {
+            None
+        } else {
+            let fiducial_side = config.fiducial.iter().find_map(|fid| match fid {
+                Fiducial::Reference(fiducial_ref) => components
+                    .iter()
+                    .find(|comp| comp.reference == *fiducial_ref)
+                    .map(|comp| comp.side),
+                Fiducial::Position(_) => None,
+            });
+            match fiducial_side {
+                Some(side) => Some(side),
+                None if sides.len() == 1 => Some(sides[0]),
+                None => {
+                    return Err(io::Error::other(
+                        "Measured fiducials of a two-sided job need fiducial designators",
+                    ))
+                }
+            }
+        };
         let multi_step = nozzles_config.len() > 1;
         let output_path = Path::new(&config.output_path);
-        let mut steps: Vec<PickAndPlaceStep> = nozzles_config
-            .into_iter()
-            .enumerate()
-            .map(|(idx, nozzle_config)| {
+        let mut steps: Vec<PickAndPlaceStep> = vec![];
+        for side in sides {
+            let fiducials = if config.fiducial.is_empty() {
+                select_fiducials(&components, side, diagnostics)
+            } else {
+                config.fiducial.clone()
+            };
+            let mut fiducial = vec![];
+            for fid in &fiducials {
+                match fid {
+                    Fiducial::Position(position) if side == Side::Bottom => {
+                        fiducial.push(Position::new(board_width - position.x, position.y))
+                    }
+                    Fiducial::Position(position) => fiducial.push(position.clone()),
+                    Fiducial::Reference(fiducial_ref) => {
+                        let fid = components
+                            .iter()
+                            .find(|fid| fid.reference == *fiducial_ref)
+                            .ok_or(io::Error::other("Fiducial not found"))?;
+                        if fid.side == side {
+                            let panel = if config.panel.explode {
+                                Size::new(
+                                    (config.panel.columns - 1) as f32,
+                                    (config.panel.rows - 1) as f32,
+                                )
+                            } else {
+                                Size::zero()
+                            };
+                            fiducial.push(Position::new(
+                                fid.position_x + panel.width * config.panel.size.width,
+                                fid.position_y + panel.height * config.panel.size.height,
+                            ));
+                        } else {
+                            diagnostics.push(
+                                Diagnostic::warning(
+                                    "fiducial-side",
+                                    format!(
+                                        "Fiducial {} is not on the {} side.",
+                                        fiducial_ref,
+                                        side.as_str()
+                                    ),
+                                )
+                                .designator(fiducial_ref),
+                            );
+                        }
+                    }
+                }
+            }
+
+            for (idx, nozzle_config) in nozzles_config.iter().enumerate() {
                 let file_name = output_path
                     .file_stem()
                     .map(|step| {
+                        let mut name = step.to_string_lossy().into_owned();
+                        if has_bottom {
+                            name = format!("{}_{}", name, side.as_str());
+                        }
                         if multi_step {
-                            format!("{}_{}", step.to_string_lossy(), idx + 1)
-                        } else {
-                            step.to_string_lossy().into_owned()
+                            name = format!("{}_{}", name, idx + 1);
                         }
+                        name
                     })
                     .unwrap();
                 let file_path = output_path
@@ -163,24 +375,30 @@ impl YY1Converter {
                     .with_extension("csv")
                     .to_string_lossy()
                     .into();
-                PickAndPlaceStep {
-                    nozzle_config,
+                steps.push(PickAndPlaceStep {
+                    side,
+                    fiducial: fiducial.clone(),
+                    nozzle_config: *nozzle_config,
                     file_path,
                     components: vec![],
                     nozzle_change: vec![],
-                }
-            })
-            .collect();
+                });
+            }
+        }
 
+        let mut bom = vec![];
         if config.bom {
-            let mut parts: HashMap<String, BOMRecord> = HashMap::new();
+            let mut parts: HashMap<(String, String), BOMRecord> = HashMap::new();
 
             for component in components.iter() {
-                if component.part.is_empty() {
+                let mpn = component.mpn();
+                if (component.part.is_empty() && mpn.is_empty())
+                    || filter.exclude_reason(component).is_some()
+                {
                     continue;
                 }
                 parts
-                    .entry(component.part.clone())
+                    .entry((component.part.clone(), mpn.into()))
                     .and_modify(|rec| {
                         rec.amount += 1;
                     })
@@ -188,73 +406,72 @@ impl YY1Converter {
                         feeder: component.feeder,
                         part: component.part.clone(),
                         amount: 1,
+                        mpn: mpn.into(),
                     });
             }
 
-            let file_name = output_path
-                .file_stem()
-                .map(|step| format!("{}_bom", step.to_string_lossy()))
-                .unwrap();
-            let file_path: String = output_path
-                .with_file_name(file_name)
-                .with_extension("csv")
-                .to_string_lossy()
-                .into();
-
-            let mut bom_writer = csv::WriterBuilder::default()
-                .terminator(csv::Terminator::CRLF)
-                .from_writer(File::create(&file_path)?);
-            let mut bom: Vec<BOMRecord> = parts.values().cloned().collect();
-            bom.sort_by(|a, b| a.part.cmp(&b.part));
-            for part in bom {
-                bom_writer.serialize(part)?
-            }
-            bom_writer.flush()?;
+            bom = parts.into_values().collect();
+            bom.sort_by(|a, b| a.part.cmp(&b.part).then_with(|| a.mpn.cmp(&b.mpn)));
         }
 
         for comp in components.iter().filter(|comp| comp.skip == 0) {
+            let mut side_steps = steps.iter_mut().filter(|step| step.side == comp.side);
             if let Some(nozzle) = comp.nozzle {
-                if let Some(step) = steps.iter_mut().find(|step| {
+                if let Some(step) = side_steps.find(|step| {
                     step.nozzle_config
                         .map(|cfg| cfg.contains(nozzle))
                         .unwrap_or(false)
                 }) {
                     step.components.push(comp.clone());
                 }
-            } else if let Some(step) = steps.first_mut() {
+            } else if let Some(step) = side_steps.next() {
                 step.components.push(comp.clone());
             }
         }
 
         Ok(Self {
-            fiducial,
             config,
+            machine,
+            overall_offset: Position::zero(),
+            measured_side,
+            pockets: PocketTracker::new(pocket_grids),
+            manual,
+            bom,
             steps,
         })
     }
 
     pub fn apply_offset(&mut self) {
-        if !self.fiducial.is_zero() {
-            let last_ofset = self
+        if self.config.overall_offset {
+            self.overall_offset = self
                 .config
                 .offset
-                .last()
+                .first()
                 .cloned()
                 .unwrap_or(Position::zero());
-            self.fiducial = Position::new(
-                self.fiducial.x + last_ofset.x,
-                self.fiducial.y + last_ofset.y,
-            );
         }
-
+        let base = &self.overall_offset;
+        let last_ofset = self
+            .config
+            .offset
+            .last()
+            .cloned()
+            .unwrap_or(Position::zero());
         let multi_offset = self.config.offset.len() > 1;
         for step in self.steps.iter_mut() {
+            for fiducial in step.fiducial.iter_mut().filter(|fid| !fid.is_zero()) {
+                *fiducial = Position::new(
+                    fiducial.x + (last_ofset.x - base.x),
+                    fiducial.y + (last_ofset.y - base.y),
+                );
+            }
+
             let components = step.components.clone();
             step.components.clear();
             for (idx, offset) in self.config.offset.iter().enumerate() {
                 for mut component in components.iter().cloned() {
-                    component.position_x += offset.x;
-                    component.position_y += offset.y;
+                    component.position_x += offset.x - base.x;
+                    component.position_y += offset.y - base.y;
                     component.reference = if multi_offset {
                         format!("{0}-{1}", component.reference, idx + 1)
                     } else {
@@ -266,6 +483,48 @@ impl YY1Converter {
         }
     }
 
+    /// Maps nominal fiducial positions onto the measured ones and moves all
+    /// components accordingly (translation for one fiducial, rotation and
+    /// scale for two).
+    ///
+    /// Only the side the fiducials were measured on is corrected. Measured
+    /// positions are machine coordinates, so the overall offset is removed first.
+    pub fn apply_correction(&mut self, diagnostics: &mut Diagnostics) -> io::Result<()> {
+        let Some(measured_side) = self.measured_side else {
+            return Ok(());
+        };
+        let base = &self.overall_offset;
+        let measured: Vec<Position> = self
+            .config
+            .measured_fiducial
+            .iter()
+            .map(|pos| Position::new(pos.x - base.x, pos.y - base.y))
+            .collect();
+        for step in self
+            .steps
+            .iter_mut()
+            .filter(|step| step.side == measured_side)
+        {
+            if step.fiducial.len() != measured.len() {
+                diagnostics.push(
+                    Diagnostic::warning(
+                        "fiducial-correction",
+                        "Fiducials not found. Skipping correction...",
+                    )
+                    .file(&step.file_path),
+                );
+                continue;
+            }
+
+            let correction = Correction::new(&step.fiducial, &measured)?;
+            for comp in step.components.iter_mut() {
+                correction.apply(comp);
+            }
+            step.fiducial = measured.clone();
+        }
+        Ok(())
+    }
+
     pub fn panelize(&mut self) {
         if !self.config.panel.explode {
             return;
@@ -289,9 +548,9 @@ impl YY1Converter {
         }
     }
 
-    pub fn assign_nozzles(&mut self) {
+    pub fn assign_nozzles(&mut self, diagnostics: &mut Diagnostics) {
         for step in self.steps.iter_mut() {
-            step.assign_nozzles();
+            step.assign_nozzles(&self.machine, diagnostics);
         }
     }
 
@@ -306,19 +565,105 @@ impl YY1Converter {
         }
     }
 
-    pub fn write_files(&self) -> io::Result<()> {
+    pub fn assign_pockets(&mut self) {
+        for step in &self.steps {
+            self.pockets.next_job();
+            for comp in step.components.iter().filter(|comp| comp.skip == 0) {
+                self.pockets.pick(comp);
+            }
+        }
+    }
+
+    /// Reports job problems before any file is written, so errors and `--strict` stop the output.
+    pub fn validate(&self, diagnostics: &mut Diagnostics) {
+        self.pockets.report(diagnostics);
+        for step in &self.steps {
+            if step.fiducial.len() > 1
+                && self.config.fiducial.len() > 1
+                && self.machine.header() == HeaderVariant::SingleFiducial
+            {
+                diagnostics.push(Diagnostic::warning(
+                    "second-fiducial",
+                    "Second fiducial needs the TwoFiducials machine header. Ignoring...",
+                ));
+            }
+            for component in &step.components {
+                if !self.machine.contains(component) {
+                    diagnostics.push(
+                        Diagnostic::warning(
+                            "outside-work-area",
+                            format!(
+                                "Component {} is outside of the machine work area.",
+                                component.reference
+                            ),
+                        )
+                        .designator(&component.reference)
+                        .file(&step.file_path),
+                    );
+                }
+            }
+        }
+    }
+
+    pub fn write_files(&self, diagnostics: &mut Diagnostics) -> io::Result<()> {
+        let output_path = Path::new(&self.config.output_path);
+        if self.config.bom {
+            let file_name = output_path
+                .file_stem()
+                .map(|step| format!("{}_bom", step.to_string_lossy()))
+                .unwrap();
+            let file_path = output_path.with_file_name(file_name).with_extension("csv");
+            let mut writer = csv::WriterBuilder::default()
+                .terminator(csv::Terminator::CRLF)
+                .from_path(&file_path)?;
+            for part in &self.bom {
+                writer.serialize(part)?
+            }
+            writer.flush()?;
+        }
+        if !self.manual.is_empty() {
+            let file_path = self.manual.write(Path::new(&self.config.output_path))?;
+            diagnostics.push(
+                Diagnostic::info(
+                    "manual-assembly",
+                    format!("{} components left for hand assembly.", self.manual.len()),
+                )
+                .file(&file_path),
+            );
+        }
+        if !self.pockets.is_empty() {
+            let file_name = output_path
+                .file_stem()
+                .map(|step| format!("{}_picks", step.to_string_lossy()))
+                .unwrap();
+            let file_path = output_path.with_file_name(file_name).with_extension("csv");
+            let mut writer = csv::WriterBuilder::default()
+                .terminator(csv::Terminator::CRLF)
+                .from_path(&file_path)?;
+            for pick in self.pockets.picks() {
+                writer.serialize(pick)?;
+            }
+            writer.flush()?;
+            diagnostics.push(
+                Diagnostic::info(
+                    "pocket-picks",
+                    "Pick positions are a report only, the machine picks from the taught feeder position.",
+                )
+                .file(&file_path.to_string_lossy()),
+            );
+        }
+
         for step in &self.steps {
             let mut writer = File::create(&step.file_path)?;
-            let mut nozzle_change = step.nozzle_change.iter().cloned();
-            let header = format!(
-                include_str!("header.csv"),
-                self.config.panel.as_string(),
-                self.fiducial.x,
-                self.fiducial.y,
-                nozzle_change.next().unwrap_or_default().as_string(),
-                nozzle_change.next().unwrap_or_default().as_string(),
-                nozzle_change.next().unwrap_or_default().as_string(),
-                nozzle_change.next().unwrap_or_default().as_string()
+            let fiducial = match self.machine.header() {
+                HeaderVariant::SingleFiducial => &step.fiducial[..step.fiducial.len().min(1)],
+                HeaderVariant::TwoFiducials => &step.fiducial[..],
+            };
+            let header = job::header(
+                &self.config.panel,
+                fiducial,
+                &self.overall_offset,
+                &step.nozzle_change,
             )
             .replace("\n", "\r\n");
             write!(&mut writer, "{header}")?;
@@ -336,7 +681,98 @@ impl YY1Converter {
     }
 }
 
+/// Picks the two fiducials farthest apart on the given side, or the only one available.
+fn select_fiducials(
+    components: &[ComponentRecord],
+    side: Side,
+    diagnostics: &mut Diagnostics,
+) -> Vec<Fiducial> {
+    let candidates: Vec<&ComponentRecord> = components
+        .iter()
+        .filter(|comp| comp.value == "Fiducial" && comp.side == side)
+        .collect();
+    let distance = |a: &ComponentRecord, b: &ComponentRecord| {
+        (a.position_x - b.position_x).hypot(a.position_y - b.position_y)
+    };
+
+    let mut selected: Vec<&ComponentRecord> = candidates.iter().take(1).copied().collect();
+    let mut max_distance = 0.0;
+    for (idx, first) in candidates.iter().enumerate() {
+        for second in &candidates[idx + 1..] {
+            let distance = distance(first, second);
+            if distance > max_distance {
+                max_distance = distance;
+                selected = vec![first, second];
+            }
+        }
+    }
+
+    if !selected.is_empty() {
+        let references: Vec<&str> = selected.iter().map(|fid| fid.reference.as_str()).collect();
+        diagnostics.push(Diagnostic::info(
+            "fiducial-selected",
+            format!(
+                "Using fiducials {} for the {} side.",
+                references.join(", "),
+                side.as_str()
+            ),
+        ));
+    }
+    selected
+        .into_iter()
+        .map(|fid| Fiducial::Reference(fid.reference.clone()))
+        .collect()
+}
+
+/// Transform from nominal to measured fiducial positions.
+struct Correction {
+    origin: Position,
+    target: Position,
+    angle: f32,
+    scale: f32,
+}
+
+impl Correction {
+    fn new(nominal: &[Position], measured: &[Position]) -> io::Result<Self> {
+        let origin = nominal[0].clone();
+        let target = measured[0].clone();
+        let (angle, scale) = match (nominal.get(1), measured.get(1)) {
+            (Some(nominal_end), Some(measured_end)) => {
+                let nominal = (nominal_end.x - origin.x, nominal_end.y - origin.y);
+                let actual = (measured_end.x - target.x, measured_end.y - target.y);
+                let nominal_len = nominal.0.hypot(nominal.1);
+                let actual_len = actual.0.hypot(actual.1);
+                if nominal_len < 1e-3 || actual_len < 1e-3 {
+                    return Err(io::Error::other("Fiducials are too close to each other"));
+                }
+                (
+                    actual.1.atan2(actual.0) - nominal.1.atan2(nominal.0),
+                    actual_len / nominal_len,
+                )
+            }
+            _ => (0.0, 1.0),
+        };
+        Ok(Self {
+            origin,
+            target,
+            angle,
+            scale,
+        })
+    }
+
+    fn apply(&self, comp: &mut ComponentRecord) {
+        let (sin, cos) = self.angle.sin_cos();
+        let dx = (comp.position_x - self.origin.x) * self.scale;
+        let dy = (comp.position_y - self.origin.y) * self.scale;
+        comp.position_x = self.target.x + dx * cos - dy * sin;
+        comp.position_y = self.target.y + dx * sin + dy * cos;
+        comp.rotation = normalize_rotation(comp.rotation + self.angle.to_degrees());
+    }
+}
+
 pub struct PickAndPlaceStep {
+    side: Side,
+    fiducial: Vec<Position>,
     file_path: String,
     components: Vec<ComponentRecord>,
     nozzle_change: Vec<NozzleChange>,
@@ -344,7 +780,7 @@ pub struct PickAndPlaceStep {
 }
 
 impl PickAndPlaceStep {
-    pub fn assign_nozzles(&mut self) {
+    pub fn assign_nozzles(&mut self, machine: &MachineProfile, diagnostics: &mut Diagnostics) {
         if let Some(nozzle_config) = self.nozzle_config {
             self.components.sort_by(|comp1, comp2| {
                 let nozzle1 = comp1.nozzle.unwrap_or(Nozzle::CN040);
@@ -368,8 +804,21 @@ impl PickAndPlaceStep {
             for component in self.components.iter_mut() {
                 if let Some(nozzle) = component.nozzle {
                     loop {
-                        match planner.plan(nozzle) {
+                        match planner.plan(nozzle, component.forced_head) {
                             PlannerAction::Head(head) => {
+                                if component.forced_head.is_some_and(|forced| forced != head) {
+                                    diagnostics.push(
+                                        Diagnostic::warning(
+                                            "forced-head",
+                                            format!(
+                                                "Unable to place component {} with {:?}.",
+                                                component.reference,
+                                                component.forced_head.unwrap()
+                                            ),
+                                        )
+                                        .designator(&component.reference),
+                                    );
+                                }
                                 component.head = head as _;
                                 break;
                             }
@@ -384,8 +833,312 @@ impl PickAndPlaceStep {
             let revert_nozzles = planner.finalize();
             for nozzle_change in revert_nozzles {
                 self.nozzle_change.push(nozzle_change);
-                self.components.push(ComponentRecord::placeholder());
+                self.components.push(machine.placeholder());
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    const FEEDERS: &str =
+        "Feeder,Package,Value,Rotation,PickHeight,PlaceHeight,MountSpeed,Nozzle,Mode,Skip,Part
+1,0402,100nF,0,0,0,100,CN040,3,0,
+9,*,*,0,0,0,100,CN040,3,0,
+";
+
+    /// Writes the test files into a directory of their own and returns its path.
+    fn fixture(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("yy1_{}_{name}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for (file, text) in files {
+            std::fs::write(dir.join(file), text).unwrap();
+        }
+        dir
+    }
+
+    fn path(dir: &Path, file: &str) -> String {
+        dir.join(file).to_string_lossy().into()
+    }
+
+    fn convert(dir: &Path, config: Config) -> YY1Converter {
+        let config = Config {
+            input_path: path(dir, "input.csv"),
+            output_path: path(dir, "output.csv"),
+            ..config
+        };
+        let mut diagnostics = Diagnostics::new(OutputFormat::Text, false);
+        YY1Converter::try_new(config, &mut diagnostics).unwrap()
+    }
+
+    fn fiducials(step: &PickAndPlaceStep) -> Vec<(f32, f32)> {
+        step.fiducial.iter().map(|fid| (fid.x, fid.y)).collect()
+    }
+
+    fn file_name(step: &PickAndPlaceStep) -> String {
+        Path::new(&step.file_path)
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .into()
+    }
+
+    fn step_references(step: &PickAndPlaceStep) -> Vec<&str> {
+        step.components
+            .iter()
+            .map(|comp| comp.reference.as_str())
+            .collect()
+    }
+
+    fn component(x: f32, y: f32, rotation: f32) -> ComponentRecord {
+        ComponentRecord {
+            position_x: x,
+            position_y: y,
+            rotation,
+            ..ComponentRecord::placeholder()
+        }
+    }
+
+    fn assert_close(comp: &ComponentRecord, x: f32, y: f32, rotation: f32) {
+        assert!((comp.position_x - x).abs() < 1e-3, "x: {}", comp.position_x);
+        assert!((comp.position_y - y).abs() < 1e-3, "y: {}", comp.position_y);
+        assert!(
+            (comp.rotation - rotation).abs() < 1e-3,
+            "rotation: {}",
+            comp.rotation
+        );
+    }
+
+    #[test]
+    fn correction_with_one_fiducial_translates() {
+        let correction =
+            Correction::new(&[Position::new(10.0, 10.0)], &[Position::new(10.5, 9.5)]).unwrap();
+        let mut comp = component(20.0, 30.0, 90.0);
+        correction.apply(&mut comp);
+        assert_close(&comp, 20.5, 29.5, 90.0);
+    }
+
+    #[test]
+    fn correction_with_two_fiducials_rotates_and_scales() {
+        let nominal = [Position::new(0.0, 0.0), Position::new(10.0, 0.0)];
+        let measured = [Position::new(5.0, 5.0), Position::new(5.0, 25.0)];
+        let correction = Correction::new(&nominal, &measured).unwrap();
+        let mut comp = component(10.0, 10.0, 0.0);
+        correction.apply(&mut comp);
+        assert_close(&comp, -15.0, 25.0, 90.0);
+    }
+
+    #[test]
+    fn correction_rejects_coincident_fiducials() {
+        let nominal = [Position::new(10.0, 10.0), Position::new(10.0, 10.0)];
+        let measured = [Position::new(10.0, 10.0), Position::new(20.0, 10.0)];
+        assert!(Correction::new(&nominal, &measured).is_err());
+        assert!(Correction::new(&measured, &nominal).is_err());
+    }
+
+    #[test]
+    fn fiducials_skip_feeder_rules() {
+        let input = "Ref,Val,Package,PosX,PosY,Rot,Side
+C1,100nF,0402,1,1,0,top
+R1,10k,0402,2,2,0,top
+FID1,Fiducial,FID,0,0,0,top
+";
+        let dir = fixture(
+            "fiducials",
+            &[("input.csv", input), ("feeders.csv", FEEDERS)],
+        );
+        let config = Config::new(String::new(), String::new())
+            .feeder_config_path(Some(path(&dir, "feeders.csv")));
+        let converter = convert(&dir, config);
+        assert_eq!(step_references(&converter.steps[0]), ["C1", "R1"]);
+        assert_eq!(converter.steps[0].components[1].feeder, 9);
+    }
+
+    #[test]
+    fn fiducials_not_manual() {
+        let input = "Ref,Val,Package,PosX,PosY,Rot,Side
+C1,100nF,0402,1,1,0,top
+FID1,Fiducial,FID,0,0,0,top
+FID2,Fiducial,FID,9,9,0,top
+J1,USB,USB-C,2,2,0,top
+";
+        let dir = fixture(
+            "fiducials_manual",
+            &[("input.csv", input), ("feeders.csv", FEEDERS)],
+        );
+        let config = Config::new(String::new(), String::new())
+            .feeder_config_path(Some(path(&dir, "feeders.csv")))
+            .exclude(vec!["FID2".into(), "J1".into()]);
+        let converter = convert(&dir, config);
+        assert_eq!(converter.manual.len(), 1);
+    }
+
+    #[test]
+    fn no_output_on_errors() {
+        let input = "Ref,Val,Package,PosX,PosY,Rot,Side
+C1,100nF,0402,1,1,0,top
+J1,USB,USB-C,2,2,0,top
+";
+        let feeders = FEEDERS.lines().take(2).collect::<Vec<_>>().join("\n");
+        let dir = fixture("strict", &[("input.csv", input), ("feeders.csv", &feeders)]);
+        let config = Config::new(path(&dir, "input.csv"), path(&dir, "output.csv"))
+            .feeder_config_path(Some(path(&dir, "feeders.csv")))
+            .bom(true);
+        let mut diagnostics = Diagnostics::new(OutputFormat::Text, true);
+        assert!(super::super::convert(config, &mut diagnostics).is_err());
+        assert!(!dir.join("output.csv").exists());
+        assert!(!dir.join("output_bom.csv").exists());
+    }
+
+    const PANEL: &str = "Ref,Val,Package,PosX,PosY,Rot,Side
+C1,100nF,0402,1,1,0,top
+FID1,Fiducial,FID,2,3,0,top
+";
+
+    fn panel_offsets(overall_offset: bool) -> YY1Converter {
+        let name = format!("panel_offsets_{overall_offset}");
+        let dir = fixture(&name, &[("input.csv", PANEL)]);
+        let config = Config::new(String::new(), String::new())
+            .fiducial(vec![Fiducial::Reference("FID1".into())])
+            .offset(vec![
+                Position::new(10.0, 20.0),
+                Position::new(110.0, 20.0),
+                Position::new(210.0, 70.0),
+            ])
+            .overall_offset(overall_offset);
+        let mut converter = convert(&dir, config);
+        converter.apply_offset();
+        converter
+    }
+
+    #[test]
+    fn overall_offset_with_multiple_offsets() {
+        let converter = panel_offsets(true);
+        assert_eq!(converter.overall_offset, Position::new(10.0, 20.0));
+        let step = &converter.steps[0];
+        // The fiducial is taken on the last unit, relative to the first one
+        assert_eq!(fiducials(step), [(202.0, 53.0)]);
+        assert_eq!(step_references(step), ["C1-1", "C1-2", "C1-3"]);
+        assert_close(&step.components[0], 1.0, 1.0, 0.0);
+        assert_close(&step.components[1], 101.0, 1.0, 0.0);
+        assert_close(&step.components[2], 201.0, 51.0, 0.0);
+    }
+
+    #[test]
+    fn multiple_offsets_without_overall_offset() {
+        let converter = panel_offsets(false);
+        assert!(converter.overall_offset.is_zero());
+        let step = &converter.steps[0];
+        assert_eq!(fiducials(step), [(212.0, 73.0)]);
+        assert_close(&step.components[0], 11.0, 21.0, 0.0);
+        assert_close(&step.components[1], 111.0, 21.0, 0.0);
+        assert_close(&step.components[2], 211.0, 71.0, 0.0);
+    }
+
+    const TWO_SIDED: &str = "Ref,Val,Package,PosX,PosY,Rot,Side
+R1,10k,0402,10,5,90,top
+R2,10k,0402,10,5,30,bottom
+FID1,Fiducial,FID,2,3,0,bottom
+";
+
+    #[test]
+    fn mirror_bottom_side() {
+        let dir = fixture("bottom", &[("input.csv", TWO_SIDED)]);
+        let config = Config::new(String::new(), String::new()).board_width(Some(50.0));
+        let converter = convert(&dir, config);
+        let [top, bottom] = &converter.steps[..] else {
+            panic!("expected top and bottom jobs");
+        };
+        assert_eq!(file_name(top), "output_top.csv");
+        assert_eq!(file_name(bottom), "output_bottom.csv");
+        assert_eq!(step_references(top), ["R1"]);
+        assert_close(&top.components[0], 10.0, 5.0, 90.0);
+        assert_eq!(step_references(bottom), ["R2"]);
+        assert_close(&bottom.components[0], 40.0, 5.0, 150.0);
+        assert_eq!(fiducials(top), []);
+        assert_eq!(fiducials(bottom), [(48.0, 3.0)]);
+    }
+
+    #[test]
+    fn mirror_fiducial_position() {
+        let dir = fixture("bottom_fiducial", &[("input.csv", TWO_SIDED)]);
+        let config = Config::new(String::new(), String::new())
+            .board_width(Some(50.0))
+            .fiducial(vec![Fiducial::Position(Position::new(4.0, 6.0))]);
+        let converter = convert(&dir, config);
+        assert_eq!(fiducials(&converter.steps[0]), [(4.0, 6.0)]);
+        assert_eq!(fiducials(&converter.steps[1]), [(46.0, 6.0)]);
+    }
+
+    #[test]
+    fn excluded_bottom_side() {
+        let dir = fixture("excluded_bottom", &[("input.csv", TWO_SIDED)]);
+        let config = Config::new(String::new(), String::new()).exclude(vec!["R2".into()]);
+        let converter = convert(&dir, config);
+        let [top] = &converter.steps[..] else {
+            panic!("expected a top job only");
+        };
+        assert_eq!(file_name(top), "output.csv");
+        assert_eq!(step_references(top), ["R1"]);
+    }
+
+    fn fiducial(reference: &str, x: f32, y: f32, side: Side) -> ComponentRecord {
+        ComponentRecord {
+            reference: reference.into(),
+            value: "Fiducial".into(),
+            side,
+            ..component(x, y, 0.0)
+        }
+    }
+
+    fn selected(components: &[ComponentRecord], side: Side) -> Vec<Fiducial> {
+        let mut diagnostics = Diagnostics::new(OutputFormat::Json, false);
+        select_fiducials(components, side, &mut diagnostics)
+    }
+
+    fn references(names: &[&str]) -> Vec<Fiducial> {
+        names
+            .iter()
+            .map(|name| Fiducial::Reference(name.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn select_farthest_fiducials() {
+        let components = [
+            fiducial("FID1", 5.0, 5.0, Side::Top),
+            fiducial("FID2", 95.0, 5.0, Side::Top),
+            component(50.0, 50.0, 0.0),
+            fiducial("FID3", 95.0, 75.0, Side::Top),
+            fiducial("FID4", 0.0, 80.0, Side::Bottom),
+        ];
+        assert_eq!(
+            selected(&components, Side::Top),
+            references(&["FID1", "FID3"])
+        );
+    }
+
+    #[test]
+    fn select_fiducials_on_side() {
+        let components = [
+            fiducial("FID1", 5.0, 5.0, Side::Top),
+            fiducial("FID2", 0.0, 0.0, Side::Bottom),
+            fiducial("FID3", 90.0, 90.0, Side::Bottom),
+        ];
+        assert_eq!(selected(&components, Side::Top), references(&["FID1"]));
+        assert_eq!(
+            selected(&components, Side::Bottom),
+            references(&["FID2", "FID3"])
+        );
+    }
+
+    #[test]
+    fn select_without_fiducials() {
+        let components = [component(5.0, 5.0, 0.0), component(10.0, 10.0, 0.0)];
+        assert_eq!(selected(&components, Side::Top), []);
+        assert_eq!(selected(&[], Side::Top), []);
+    }
+}
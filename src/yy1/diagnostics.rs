@@ -0,0 +1,231 @@
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "Info",
+            Severity::Warning => "Warning",
+            Severity::Error => "Error",
+        }
+    }
+}
+
+/// Problem or notice found while converting, e.g. a component without a feeder.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct Diagnostic {
+    severity: Severity,
+    code: &'static str,
+    designator: Option<String>,
+    file: Option<String>,
+    line: Option<usize>,
+    message: String,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            code,
+            designator: None,
+            file: None,
+            line: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn info(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(Severity::Info, code, message)
+    }
+
+    pub fn warning(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, code, message)
+    }
+
+    pub fn error(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, code, message)
+    }
+
+    pub fn designator(self, val: &str) -> Self {
+        Self {
+            designator: Some(val.into()),
+            ..self
+        }
+    }
+
+    pub fn file(self, val: &str) -> Self {
+        Self {
+            file: Some(val.into()),
+            ..self
+        }
+    }
+
+    pub fn line(self, val: usize) -> Self {
+        Self {
+            line: Some(val),
+            ..self
+        }
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+impl From<Diagnostic> for io::Error {
+    fn from(diagnostic: Diagnostic) -> Self {
+        io::Error::other(diagnostic)
+    }
+}
+
+impl From<io::Error> for Diagnostic {
+    fn from(err: io::Error) -> Self {
+        match err
+            .get_ref()
+            .and_then(|inner| inner.downcast_ref::<Diagnostic>())
+        {
+            Some(diagnostic) => diagnostic.clone(),
+            None => Diagnostic::error("fatal", err.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[{}]: ", self.severity.as_str(), self.code)?;
+        match (&self.file, self.line) {
+            (Some(file), Some(line)) => write!(f, "{file}:{line}: ")?,
+            (Some(file), None) => write!(f, "{file}: ")?,
+            _ => {}
+        }
+        if let Some(designator) = &self.designator {
+            write!(f, "{designator}: ")?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(val: &str) -> Result<Self, Self::Err> {
+        match val {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(format!("Invalid format: {val}")),
+        }
+    }
+}
+
+/// Diagnostics collected during a run.
+///
+/// Text diagnostics are printed as they arrive; JSON is written as one array when the run ends.
+/// Both go to stderr, as stdout carries the `--inspect` report.
+/// With `strict` enabled, warnings are reported as errors.
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    items: Vec<Diagnostic>,
+    format: OutputFormat,
+    strict: bool,
+}
+
+impl Diagnostics {
+    pub fn new(format: OutputFormat, strict: bool) -> Self {
+        Self {
+            items: vec![],
+            format,
+            strict,
+        }
+    }
+
+    pub fn push(&mut self, mut diagnostic: Diagnostic) {
+        if self.strict && diagnostic.severity == Severity::Warning {
+            diagnostic.severity = Severity::Error;
+        }
+        if self.format == OutputFormat::Text {
+            eprintln!("{diagnostic}");
+        }
+        self.items.push(diagnostic);
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.items
+            .iter()
+            .any(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+
+    /// Fails if an error was reported, so that no output is written.
+    pub fn check(&self) -> io::Result<()> {
+        if self.has_errors() {
+            let message = "Output not written because of errors.";
+            return Err(Diagnostic::error("not-written", message).into());
+        }
+        Ok(())
+    }
+
+    /// Writes the collected diagnostics for JSON output.
+    pub fn finish(&self) -> io::Result<()> {
+        if self.format == OutputFormat::Json {
+            let json = serde_json::to_string_pretty(&self.items).map_err(io::Error::other)?;
+            eprintln!("{json}");
+        }
+        Ok(())
+    }
+
+    /// Exit code: 0 on success, 1 if any error was reported (including warnings in strict mode).
+    pub fn exit_code(&self) -> u8 {
+        if self.has_errors() {
+            1
+        } else {
+            0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_matches_json_fields() {
+        let diagnostic = Diagnostic::warning("feeder-empty", "Feeder #3 is empty.")
+            .designator("C3")
+            .file("feeders.csv")
+            .line(4);
+        assert_eq!(
+            diagnostic.to_string(),
+            "Warning[feeder-empty]: feeders.csv:4: C3: Feeder #3 is empty."
+        );
+        assert_eq!(
+            Diagnostic::info("fiducial-selected", "Using FID1.").to_string(),
+            "Info[fiducial-selected]: Using FID1."
+        );
+    }
+
+    #[test]
+    fn strict_promotes_warnings() {
+        let mut diagnostics = Diagnostics::new(OutputFormat::Json, false);
+        diagnostics.push(Diagnostic::warning("feeder-empty", "Feeder #3 is empty."));
+        assert_eq!(diagnostics.exit_code(), 0);
+
+        let mut diagnostics = Diagnostics::new(OutputFormat::Json, true);
+        diagnostics.push(Diagnostic::info("fiducial-selected", "Using FID1."));
+        assert_eq!(diagnostics.exit_code(), 0);
+        diagnostics.push(Diagnostic::warning("feeder-empty", "Feeder #3 is empty."));
+        assert_eq!(diagnostics.exit_code(), 1);
+    }
+}
@@ -0,0 +1,54 @@
+use super::input::*;
+use super::*;
+
+pub fn read_mount(text: &str, side: Side, units: Units) -> io::Result<Vec<KiCadRecord>> {
+    let mut records = vec![];
+    for (idx, line) in text.lines().enumerate() {
+        let line_no = idx + 1;
+        let fields = split_fields(line);
+        if fields.is_empty() {
+            continue;
+        }
+        // Value is empty for fiducials and test points
+        if fields.len() < 5 {
+            return Err(invalid_input_at("Missing fields", line_no));
+        }
+        let package_idx = fields.len() - 1;
+        records.push(KiCadRecord {
+            reference: fields[0].clone(),
+            position_x: parse_length(&fields[1], units, line_no)?,
+            position_y: parse_length(&fields[2], units, line_no)?,
+            rotation: parse_number(&fields[3], line_no)?,
+            value: fields[4..package_idx].join(" "),
+            package: fields[package_idx].clone(),
+            side,
+            ..Default::default()
+        });
+    }
+
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_mount_lines() {
+        let text = "C1 10.16 5.08 90 100nF 16V C0402\nFID1 5 5 0 FIDUCIAL-1MM\n\n";
+        let records = read_mount(text, Side::Bottom, Units::Millimeters).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].value, "100nF 16V");
+        assert_eq!(records[0].package, "C0402");
+        assert_eq!(records[0].rotation, 90.0);
+        assert_eq!(records[0].side, Side::Bottom);
+        assert_eq!(records[1].reference, "FID1");
+        assert_eq!(records[1].value, "");
+        assert_eq!(records[1].package, "FIDUCIAL-1MM");
+    }
+
+    #[test]
+    fn reject_short_lines() {
+        assert!(read_mount("FID1 5 5 0\n", Side::Top, Units::Millimeters).is_err());
+    }
+}
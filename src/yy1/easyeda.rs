@@ -0,0 +1,98 @@
+use super::input::*;
+use super::*;
+
+pub fn is_header(line: &str) -> bool {
+    let line = line.trim_start_matches('"');
+    line.starts_with("Designator") && line.contains("Mid X")
+}
+
+pub fn read_cpl(text: &str, units: Units) -> io::Result<Vec<KiCadRecord>> {
+    let lines: Vec<&str> = text.lines().collect();
+    let header_idx = lines
+        .iter()
+        .position(|line| is_header(line))
+        .ok_or_else(|| invalid_input("CPL header not found"))?;
+    let rows = read_rows(&lines[header_idx..], detect_delimiter(lines[header_idx]))?;
+    let ((_, header), rows) = rows
+        .split_first()
+        .ok_or_else(|| invalid_input("CPL header not found"))?;
+    let header_line = header_idx + 1;
+
+    let column = |name: &str| header.iter().position(|column| column == name);
+    let required = |name: &str| {
+        column(name).ok_or_else(|| invalid_input_at(format!("Missing {name} column"), header_line))
+    };
+    let reference = required("Designator")?;
+    let position_x = required("Mid X")?;
+    let position_y = required("Mid Y")?;
+    let layer = required("Layer")?;
+    let rotation = required("Rotation")?;
+    let value = column("Comment").or(column("Value"));
+    let package = column("Footprint").or(column("Package"));
+
+    let mut records = vec![];
+    for (line, row) in rows {
+        let line_no = header_idx + line;
+        if row.iter().all(String::is_empty) {
+            continue;
+        }
+        let field = |idx: usize| {
+            row.get(idx)
+                .ok_or_else(|| invalid_input_at("Missing field", line_no))
+        };
+        let optional = |idx: Option<usize>| {
+            idx.and_then(|idx| row.get(idx))
+                .cloned()
+                .unwrap_or_default()
+        };
+        let layer_name = field(layer)?;
+        let side = parse_side(layer_name)
+            .ok_or_else(|| invalid_input_at(format!("Invalid layer \"{layer_name}\""), line_no))?;
+        records.push(KiCadRecord {
+            reference: field(reference)?.clone(),
+            value: optional(value),
+            package: optional(package),
+            position_x: parse_length(field(position_x)?, units, line_no)?,
+            position_y: parse_length(field(position_y)?, units, line_no)?,
+            rotation: parse_number(field(rotation)?, line_no)?,
+            side,
+            ..Default::default()
+        });
+    }
+
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_cpl_file() {
+        let text = "Designator\tFootprint\tMid X\tMid Y\tRef X\tRef Y\tPad X\tPad Y\tLayer\tRotation\tComment
+C1\tC0402\t10.5mm\t-3mm\t10mm\t-3mm\t10mm\t-3mm\tT\t90\t100nF
+U1\tSOIC-8\t400mil\t200mil\t0\t0\t0\t0\tB\t180\tLM358
+";
+        let records = read_cpl(text, Units::Millimeters).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].reference, "C1");
+        assert_eq!(records[0].value, "100nF");
+        assert_eq!(records[0].package, "C0402");
+        assert_eq!((records[0].position_x, records[0].position_y), (10.5, -3.0));
+        assert_eq!(records[0].side, Side::Top);
+        assert!((records[1].position_x - 10.16).abs() < 1e-4);
+        assert_eq!(records[1].rotation, 180.0);
+        assert_eq!(records[1].side, Side::Bottom);
+    }
+
+    #[test]
+    fn optional_value_and_package() {
+        let text = "Designator,Mid X,Mid Y,Layer,Rotation\nR1,1,2,Top,0\n";
+        let records = read_cpl(text, Units::Inches).unwrap();
+        assert_eq!(records[0].value, "");
+        assert_eq!(records[0].package, "");
+        assert_eq!(records[0].position_y, 50.8);
+        let text = "Designator,Mid X,Mid Y,Rotation\nR1,1,2,0\n";
+        assert!(read_cpl(text, Units::Millimeters).is_err());
+    }
+}
@@ -0,0 +1,247 @@
+use super::filter::glob;
+use super::library::PartLibrary;
+use super::values::ValueNormalizer;
+use super::*;
+use regex::Regex;
+
+type FeederKey = (String, String);
+
+/// Feeder stored under its unitless key, with its normalized value and whether it has a unit.
+type UnitlessEntry = (String, bool, FeederConfig);
+
+/// Value or package field of a feeder rule: `/regex/`, a glob with `*` and `?`, or plain text.
+enum Pattern {
+    Literal(String),
+    Regex(Regex),
+}
+
+impl Pattern {
+    fn parse(val: &str) -> Result<Option<Self>, String> {
+        let regex = match val.strip_prefix('/').and_then(|val| val.strip_suffix('/')) {
+            Some(regex) => regex.to_string(),
+            None if val.contains(['*', '?']) => return Ok(Some(Pattern::Regex(glob(val)))),
+            None => return Ok(None),
+        };
+        Regex::new(&regex)
+            .map(|re| Some(Pattern::Regex(re)))
+            .map_err(|err| format!("Invalid feeder pattern \"{val}\": {err}"))
+    }
+
+    fn is_match(&self, val: &str) -> bool {
+        match self {
+            Pattern::Literal(literal) => literal == val,
+            Pattern::Regex(re) => re.is_match(val),
+        }
+    }
+}
+
+struct FeederRule {
+    value: Pattern,
+    package: Pattern,
+    config: FeederConfig,
+}
+
+/// Feeder lookup by manufacturer part number, value and package.
+///
+/// Slots holding a library part match components by manufacturer part number first. Exact
+/// matches take precedence over pattern rules, which are tried in file order, and a `*`/`*` row
+/// is the default for everything else. Exact matches also use normalized values: a component
+/// value without a unit (`100n`) matches a feeder with a unit (`100nF`) and vice versa, as long
+/// as the match is unambiguous. Values with different units (`100nF`, `100nH`) never match.
+pub struct FeederTable {
+    normalizer: ValueNormalizer,
+    parts: HashMap<String, FeederConfig>,
+    exact: HashMap<FeederKey, FeederConfig>,
+    normalized: HashMap<FeederKey, FeederConfig>,
+    unitless: HashMap<FeederKey, Vec<UnitlessEntry>>,
+    rules: Vec<FeederRule>,
+    default: Option<FeederConfig>,
+}
+
+impl FeederTable {
+    pub fn new(
+        records: Vec<FeederConfig>,
+        normalizer: ValueNormalizer,
+        library: &PartLibrary,
+        path: &str,
+    ) -> io::Result<Self> {
+        let mut parts = HashMap::new();
+        let mut exact_records = vec![];
+        let mut rules = vec![];
+        let mut default = None;
+        for cfg in records {
+            if library.contains(&cfg.part) {
+                parts.insert(cfg.part.clone(), cfg.clone());
+                if cfg.value.is_empty() {
+                    continue;
+                }
+            }
+            if cfg.value == "*" && cfg.package == "*" {
+                default = Some(cfg);
+                continue;
+            }
+            let pattern = |val: &str| {
+                Pattern::parse(val).map_err(|message| {
+                    Diagnostic::error("feeder-pattern", message)
+                        .file(path)
+                        .line(cfg.line)
+                })
+            };
+            match (pattern(&cfg.value)?, pattern(&cfg.package)?) {
+                (None, None) => exact_records.push(cfg),
+                (value, package) => rules.push(FeederRule {
+                    value: value
+                        .unwrap_or_else(|| Pattern::Literal(normalizer.normalize(&cfg.value))),
+                    package: package.unwrap_or_else(|| Pattern::Literal(cfg.package.clone())),
+                    config: cfg,
+                }),
+            }
+        }
+
+        let mut normalized = HashMap::new();
+        let mut unitless: HashMap<FeederKey, Vec<UnitlessEntry>> = HashMap::new();
+        for cfg in &exact_records {
+            let package = cfg.package.clone();
+            let value = normalizer.normalize(&cfg.value);
+            normalized
+                .entry((value.clone(), package.clone()))
+                .or_insert(cfg.clone());
+            unitless
+                .entry((normalizer.normalize_unitless(&cfg.value), package))
+                .or_default()
+                .push((value, normalizer.has_unit(&cfg.value), cfg.clone()));
+        }
+        let exact = exact_records
+            .into_iter()
+            .map(|cfg| ((cfg.value.clone(), cfg.package.clone()), cfg))
+            .collect();
+
+        Ok(Self {
+            normalizer,
+            parts,
+            exact,
+            normalized,
+            unitless,
+            rules,
+            default,
+        })
+    }
+
+    pub fn get(&self, value: &str, package: &str, mpn: &str) -> Option<&FeederConfig> {
+        self.parts
+            .get(mpn)
+            .or_else(|| self.exact.get(&(value.into(), package.into())))
+            .or_else(|| {
+                let key = (self.normalizer.normalize(value), package.into());
+                self.normalized.get(&key)
+            })
+            .or_else(|| self.get_unitless(value, package))
+            .or_else(|| {
+                let normalized = self.normalizer.normalize(value);
+                self.rules
+                    .iter()
+                    .find(|rule| {
+                        let value = match rule.value {
+                            Pattern::Literal(_) => &normalized,
+                            Pattern::Regex(_) => value,
+                        };
+                        rule.value.is_match(value) && rule.package.is_match(package)
+                    })
+                    .map(|rule| &rule.config)
+            })
+            .or(self.default.as_ref())
+    }
+
+    /// Matches ignoring the unit if the component or the feeder value has none.
+    fn get_unitless(&self, value: &str, package: &str) -> Option<&FeederConfig> {
+        let key = (self.normalizer.normalize_unitless(value), package.into());
+        let has_unit = self.normalizer.has_unit(value);
+        let mut candidates = self
+            .unitless
+            .get(&key)?
+            .iter()
+            .filter(|(_, feeder_unit, _)| !has_unit || !feeder_unit);
+        let (first, _, cfg) = candidates.next()?;
+        candidates
+            .all(|(other, _, _)| other == first)
+            .then_some(cfg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(csv: &str) -> FeederTable {
+        table_with_library(csv, &PartLibrary::default())
+    }
+
+    fn table_with_library(csv: &str, library: &PartLibrary) -> FeederTable {
+        let mut reader = csv::ReaderBuilder::new()
+            .flexible(true)
+            .from_reader(csv.as_bytes());
+        let records = reader.deserialize().collect::<Result<_, _>>().unwrap();
+        FeederTable::new(records, ValueNormalizer::default(), library, "feeders.csv").unwrap()
+    }
+
+    fn feeder(table: &FeederTable, value: &str, package: &str) -> Option<u8> {
+        table.get(value, package, "").map(|cfg| cfg.feeder)
+    }
+
+    #[test]
+    fn exact_match_takes_precedence() {
+        let table = table("Feeder,Package,Value\n1,0402,100n\n2,0402,100nF\n3,0402,*\n4,*,*\n");
+        assert_eq!(feeder(&table, "100nF", "0402"), Some(2));
+        assert_eq!(feeder(&table, "100n", "0402"), Some(1));
+        assert_eq!(feeder(&table, "0.1uF", "0402"), Some(2));
+        assert_eq!(feeder(&table, "10k", "0402"), Some(3));
+        assert_eq!(feeder(&table, "10k", "0603"), Some(4));
+    }
+
+    #[test]
+    fn unitless_value_matches_feeder_with_unit() {
+        let table = table("Feeder,Package,Value\n1,0402,100nF\n2,0603,100n\n");
+        assert_eq!(feeder(&table, "100n", "0402"), Some(1));
+        assert_eq!(feeder(&table, "100nF", "0603"), Some(2));
+    }
+
+    #[test]
+    fn different_units_do_not_match() {
+        let table = table("Feeder,Package,Value\n5,0402,100nH\n");
+        assert_eq!(feeder(&table, "100nF", "0402"), None);
+        assert_eq!(feeder(&table, "100n", "0402"), Some(5));
+    }
+
+    #[test]
+    fn ambiguous_unitless_value_does_not_match() {
+        let table = table("Feeder,Package,Value\n1,0402,100nF\n2,0402,100nH\n");
+        assert_eq!(feeder(&table, "100n", "0402"), None);
+        assert_eq!(feeder(&table, "100nH", "0402"), Some(2));
+    }
+
+    #[test]
+    fn rules_are_tried_in_file_order() {
+        let table = table("Feeder,Package,Value\n1,SOT-23*,/^BC8[0-9]+/\n2,SOT-23*,*\n");
+        assert_eq!(feeder(&table, "BC847", "SOT-23-3"), Some(1));
+        assert_eq!(feeder(&table, "MMBT3904", "SOT-23-3"), Some(2));
+        assert_eq!(feeder(&table, "BC847", "SOT-223"), None);
+        assert_eq!(feeder(&table, "BC847", "SOD-123"), None);
+    }
+
+    #[test]
+    fn part_number_first() {
+        let library = PartLibrary::parse("MPN,Nozzle\nGRM155R71C104KA88D,CN040\nLM358DR,CN140\n");
+        let table = table_with_library(
+            "Feeder,Package,Value,Part\n1,0402,100nF,\n2,,,GRM155R71C104KA88D\n\
+             3,SOIC-8,LM358,LM358DR\n4,*,*,\n",
+            &library.unwrap(),
+        );
+        let feeder = |value, package, mpn| table.get(value, package, mpn).map(|cfg| cfg.feeder);
+        assert_eq!(feeder("100nF", "0402", "GRM155R71C104KA88D"), Some(2));
+        assert_eq!(feeder("100nF", "0402", ""), Some(1));
+        assert_eq!(feeder("100nF", "0402", "CL05B104KO5NNNC"), Some(1));
+        assert_eq!(feeder("", "", "GRM155R71C104KA88D"), Some(2));
+        assert_eq!(feeder("LM358", "SOIC-8", ""), Some(3));
+        assert_eq!(feeder("10k", "0402", ""), Some(4));
+    }
+}
@@ -0,0 +1,201 @@
+use super::*;
+use regex::Regex;
+
+const VARIANT_FIELDS: [&str; 3] = ["Variant", "Variants", "Config"];
+
+/// Converts a glob with `*` and `?` into an anchored regex.
+pub fn glob(pattern: &str) -> Regex {
+    let re: String = pattern
+        .chars()
+        .map(|ch| match ch {
+            '*' => ".*".into(),
+            '?' => ".".into(),
+            ch => regex::escape(&ch.to_string()),
+        })
+        .collect();
+    Regex::new(&format!("^{re}$")).unwrap()
+}
+
+/// Component filter rule like `J*`, `value=DNP` or `package=TestPoint*`.
+struct FilterRule {
+    spec: String,
+    field: String,
+    pattern: Regex,
+}
+
+impl FilterRule {
+    fn new(spec: &str) -> Self {
+        let (field, pattern) = spec.split_once('=').unwrap_or(("designator", spec));
+        Self {
+            spec: spec.into(),
+            field: field.trim().to_lowercase(),
+            pattern: glob(pattern.trim()),
+        }
+    }
+
+    fn is_match(&self, comp: &ComponentRecord) -> bool {
+        let val = match self.field.as_str() {
+            "designator" | "ref" | "reference" => Some(comp.reference.as_str()),
+            "value" | "val" => Some(comp.value.as_str()),
+            "package" | "footprint" => Some(comp.package.as_str()),
+            field => comp
+                .fields
+                .iter()
+                .find(|(name, _)| name.to_lowercase() == field)
+                .map(|(_, val)| val.as_str()),
+        };
+        val.is_some_and(|val| self.pattern.is_match(val))
+    }
+}
+
+/// Selects the components to place by DNP state, include/exclude rules and assembly variant.
+pub struct ComponentFilter {
+    include: Vec<FilterRule>,
+    exclude: Vec<FilterRule>,
+    variant: Option<String>,
+}
+
+impl ComponentFilter {
+    pub fn new(include: &[String], exclude: &[String], variant: Option<String>) -> Self {
+        Self {
+            include: include.iter().map(|spec| FilterRule::new(spec)).collect(),
+            exclude: exclude.iter().map(|spec| FilterRule::new(spec)).collect(),
+            variant,
+        }
+    }
+
+    /// Returns why the component is excluded, or `None` if it should be placed.
+    pub fn exclude_reason(&self, comp: &ComponentRecord) -> Option<String> {
+        if comp.dnp {
+            return Some("Not populated".into());
+        }
+        if !self.include.is_empty() && !self.include.iter().any(|rule| rule.is_match(comp)) {
+            return Some("Not included".into());
+        }
+        if let Some(rule) = self.exclude.iter().find(|rule| rule.is_match(comp)) {
+            return Some(format!("Excluded by {}", rule.spec));
+        }
+        if let Some(variant) = &self.variant {
+            if !self.is_fitted(comp, variant) {
+                return Some(format!("Not fitted in variant {variant}"));
+            }
+        }
+        None
+    }
+
+    /// Checks the variant field: `+A` or `A` fits only in A, `-A` is not fitted in A.
+    fn is_fitted(&self, comp: &ComponentRecord, variant: &str) -> bool {
+        let tokens: Vec<&str> = VARIANT_FIELDS
+            .iter()
+            .filter_map(|name| comp.fields.get(*name))
+            .flat_map(|val| val.split([',', ' ']))
+            .map(str::trim)
+            .filter(|token| !token.is_empty())
+            .collect();
+        if tokens
+            .iter()
+            .any(|token| token.strip_prefix('-') == Some(variant))
+        {
+            return false;
+        }
+        let fitted: Vec<&str> = tokens
+            .iter()
+            .filter(|token| !token.starts_with('-'))
+            .map(|token| token.trim_start_matches('+'))
+            .collect();
+        fitted.is_empty() || fitted.contains(&variant)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn component(
+        reference: &str,
+        value: &str,
+        package: &str,
+        fields: &[(&str, &str)],
+    ) -> ComponentRecord {
+        ComponentRecord {
+            reference: reference.into(),
+            value: value.into(),
+            package: package.into(),
+            fields: fields
+                .iter()
+                .map(|(name, val)| (name.to_string(), val.to_string()))
+                .collect(),
+            ..ComponentRecord::placeholder()
+        }
+    }
+
+    fn rules(specs: &[&str]) -> Vec<String> {
+        specs.iter().map(|spec| spec.to_string()).collect()
+    }
+
+    #[test]
+    fn glob_is_anchored() {
+        assert!(glob("TP*").is_match("TP12"));
+        assert!(!glob("TP*").is_match("XTP12"));
+        assert!(glob("R?").is_match("R1"));
+        assert!(!glob("R?").is_match("R10"));
+        assert!(glob("C_0402*").is_match("C_0402_1005Metric"));
+    }
+
+    #[test]
+    fn exclude_dnp() {
+        let filter = ComponentFilter::new(&[], &[], None);
+        let mut comp = component("R1", "10k", "0402", &[]);
+        assert_eq!(filter.exclude_reason(&comp), None);
+        comp.dnp = true;
+        assert_eq!(filter.exclude_reason(&comp), Some("Not populated".into()));
+    }
+
+    #[test]
+    fn include_and_exclude_rules() {
+        let filter = ComponentFilter::new(
+            &rules(&["R*", "value=100n"]),
+            &rules(&["R9", "package=TestPoint*", "Mfr=Acme"]),
+            None,
+        );
+        assert_eq!(
+            filter.exclude_reason(&component("R1", "10k", "0402", &[])),
+            None
+        );
+        assert_eq!(
+            filter.exclude_reason(&component("C1", "100n", "0402", &[])),
+            None
+        );
+        assert_eq!(
+            filter.exclude_reason(&component("J1", "CONN", "JST", &[])),
+            Some("Not included".into())
+        );
+        assert_eq!(
+            filter.exclude_reason(&component("R9", "10k", "0402", &[])),
+            Some("Excluded by R9".into())
+        );
+        assert_eq!(
+            filter.exclude_reason(&component("R2", "10k", "TestPoint_1mm", &[])),
+            Some("Excluded by package=TestPoint*".into())
+        );
+        assert_eq!(
+            filter.exclude_reason(&component("R3", "10k", "0402", &[("MFR", "Acme")])),
+            Some("Excluded by Mfr=Acme".into())
+        );
+    }
+
+    #[test]
+    fn variant_fields() {
+        let filter = ComponentFilter::new(&[], &[], Some("A".into()));
+        let fitted = |variant: &str| {
+            let comp = component("R1", "10k", "0402", &[("Variant", variant)]);
+            filter.exclude_reason(&comp).is_none()
+        };
+        assert!(fitted(""));
+        assert!(fitted("A"));
+        assert!(fitted("+A, B"));
+        assert!(!fitted("B"));
+        assert!(!fitted("-A"));
+        assert!(fitted("-B"));
+    }
+}
@@ -0,0 +1,321 @@
+use super::mapping::ColumnMapping;
+use super::*;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Default)]
+pub struct Placement {
+    pub records: Vec<KiCadRecord>,
+    pub board_width: Option<f32>,
+}
+
+impl From<Vec<KiCadRecord>> for Placement {
+    fn from(records: Vec<KiCadRecord>) -> Self {
+        Self {
+            records,
+            board_width: None,
+        }
+    }
+}
+
+pub fn read_placement(config: &Config) -> io::Result<Placement> {
+    let path = &config.input_path;
+    let mut placement = match &config.column_map_path {
+        Some(map_path) => ColumnMapping::load(map_path, config.input_units)?
+            .read(&read_text(path)?)
+            .map(Placement::from)
+            .map_err(|err| in_file(path, err))?,
+        None => read_placement_file(path, config.input_units).map_err(|err| in_file(path, err))?,
+    };
+    if let Some(path) = &config.design_bom_path {
+        let design_bom = bom::read_design_bom(path)?;
+        for record in placement.records.iter_mut() {
+            if let Some(entry) = design_bom.get(&record.reference) {
+                entry.apply(record);
+            }
+        }
+    }
+    Ok(placement)
+}
+
+fn read_placement_file(path: &str, units: Units) -> io::Result<Placement> {
+    let text = read_text(path)?;
+    match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+        Some("mnt") => return eagle::read_mount(&text, Side::Top, units).map(Placement::from),
+        Some("mnb") => return eagle::read_mount(&text, Side::Bottom, units).map(Placement::from),
+        _ => {}
+    }
+
+    if text.lines().any(altium::is_header) {
+        return altium::read_pick_place(&text).map(Placement::from);
+    }
+
+    if text.lines().any(easyeda::is_header) {
+        return easyeda::read_cpl(&text, units).map(Placement::from);
+    }
+
+    let first_line = text.lines().map(str::trim).find(|line| !line.is_empty());
+    match first_line {
+        Some(line) if line.starts_with("(kicad_pcb") => kicad::read_pcb(&text),
+        Some(line) if line.starts_with('#') => kicad::read_pos(&text).map(Placement::from),
+        _ => {
+            let mut records: Vec<KiCadRecord> = parse_csv(&text, "invalid-input")?
+                .into_iter()
+                .map(|(_, record)| record)
+                .collect();
+            for record in records.iter_mut() {
+                record.position_x *= units.scale();
+                record.position_y *= units.scale();
+            }
+            Ok(Placement::from(records))
+        }
+    }
+}
+
+/// Error in a placement or BOM file.
+pub fn invalid_input(message: impl Into<String>) -> io::Error {
+    Diagnostic::error("invalid-input", message).into()
+}
+
+/// Error in a placement or BOM file, at the given line.
+pub fn invalid_input_at(message: impl Into<String>, line: usize) -> io::Error {
+    Diagnostic::error("invalid-input", message)
+        .line(line)
+        .into()
+}
+
+/// Points a reader error at the file it was read from.
+pub fn in_file(path: &str, err: io::Error) -> io::Error {
+    Diagnostic::from(err).file(path).into()
+}
+
+pub fn read_text(path: &str) -> io::Result<String> {
+    let bytes = fs::read(path)
+        .map_err(|err| Diagnostic::error("unreadable-file", err.to_string()).file(path))?;
+    let invalid = |err: String| Diagnostic::error("invalid-encoding", err).file(path);
+    let utf16 = |bytes: &[u8], decode: fn([u8; 2]) -> u16| {
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|pair| decode([pair[0], pair[1]]))
+            .collect();
+        String::from_utf16(&units).map_err(|err| invalid(err.to_string()))
+    };
+    let text = match bytes.as_slice() {
+        [0xff, 0xfe, rest @ ..] => utf16(rest, u16::from_le_bytes),
+        [0xfe, 0xff, rest @ ..] => utf16(rest, u16::from_be_bytes),
+        [0xef, 0xbb, 0xbf, rest @ ..] => {
+            String::from_utf8(rest.to_vec()).map_err(|err| invalid(err.to_string()))
+        }
+        _ => String::from_utf8(bytes).map_err(|err| invalid(err.to_string())),
+    };
+    Ok(text?)
+}
+
+/// Reads a CSV config file with the line number of each record.
+///
+/// Malformed records are reported as `code` diagnostics pointing at the line.
+pub fn read_csv<T: serde::de::DeserializeOwned>(
+    path: &str,
+    code: &'static str,
+) -> io::Result<Vec<(usize, T)>> {
+    parse_csv(&read_text(path)?, code).map_err(|err| in_file(path, err))
+}
+
+/// Parses CSV config text, see [`read_csv`].
+pub fn parse_csv<T: serde::de::DeserializeOwned>(
+    text: &str,
+    code: &'static str,
+) -> io::Result<Vec<(usize, T)>> {
+    let error = |err: csv::Error| -> io::Error {
+        let diagnostic = Diagnostic::error(code, csv_message(&err));
+        match err.position() {
+            Some(pos) => diagnostic.line(line_at(text, pos.byte())),
+            None => diagnostic,
+        }
+        .into()
+    };
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(text.as_bytes());
+    let headers = reader.headers().map_err(error)?.clone();
+    let mut records = vec![];
+    for record in reader.records() {
+        let record = record.map_err(error)?;
+        let line = record.position().map_or(0, |pos| line_at(text, pos.byte()));
+        let val = record
+            .deserialize(Some(&headers))
+            .map_err(|err| Diagnostic::error(code, csv_message(&err)).line(line))?;
+        records.push((line, val));
+    }
+    Ok(records)
+}
+
+/// Line number of the record starting at `byte`.
+///
+/// csv positions point before the blank lines it skips, so those are skipped here as well.
+pub fn line_at(text: &str, byte: u64) -> usize {
+    let start = (byte as usize).min(text.len());
+    let end = start + text[start..].len() - text[start..].trim_start_matches(['\r', '\n']).len();
+    text.as_bytes()[..end]
+        .iter()
+        .filter(|ch| **ch == b'\n')
+        .count()
+        + 1
+}
+
+/// Message of a csv error, without the record position csv adds to it.
+pub fn csv_message(err: &csv::Error) -> String {
+    match err.kind() {
+        csv::ErrorKind::Deserialize { err, .. } => err.to_string(),
+        _ => err.to_string(),
+    }
+}
+
+pub fn detect_delimiter(header: &str) -> Option<u8> {
+    [b'\t', b',', b';']
+        .into_iter()
+        .find(|delimiter| header.contains(*delimiter as char))
+}
+
+/// Splits delimited or whitespace separated lines into rows of fields.
+///
+/// Each row comes with its line number, counted from 1 at the first of `lines`.
+pub fn read_rows(lines: &[&str], delimiter: Option<u8>) -> io::Result<Vec<(usize, Vec<String>)>> {
+    match delimiter {
+        Some(delimiter) => {
+            let text = lines.join("\n");
+            let mut reader = csv::ReaderBuilder::new()
+                .has_headers(false)
+                .flexible(true)
+                .delimiter(delimiter)
+                .trim(csv::Trim::All)
+                .from_reader(text.as_bytes());
+            reader
+                .records()
+                .map(|record| {
+                    let record = record.map_err(|err| {
+                        let line = err.position().map_or(0, |pos| line_at(&text, pos.byte()));
+                        invalid_input_at(csv_message(&err), line)
+                    })?;
+                    let line = record
+                        .position()
+                        .map_or(0, |pos| line_at(&text, pos.byte()));
+                    Ok((line, record.iter().map(String::from).collect()))
+                })
+                .collect()
+        }
+        None => Ok(lines
+            .iter()
+            .enumerate()
+            .map(|(idx, line)| (idx + 1, split_fields(line)))
+            .collect()),
+    }
+}
+
+pub fn split_fields(line: &str) -> Vec<String> {
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut in_field = false;
+    for ch in line.chars() {
+        match ch {
+            '"' => {
+                quoted = !quoted;
+                in_field = true;
+            }
+            ch if ch.is_whitespace() && !quoted => {
+                if in_field {
+                    fields.push(std::mem::take(&mut field));
+                    in_field = false;
+                }
+            }
+            ch => {
+                field.push(ch);
+                in_field = true;
+            }
+        }
+    }
+    if in_field {
+        fields.push(field);
+    }
+    fields
+}
+
+pub fn parse_number(val: &str, line: usize) -> io::Result<f32> {
+    val.parse()
+        .map_err(|_| invalid_input_at(format!("Invalid number \"{val}\""), line))
+}
+
+pub fn parse_length(val: &str, units: Units, line: usize) -> io::Result<f32> {
+    units
+        .parse_length(val)
+        .ok_or_else(|| invalid_input_at(format!("Invalid length \"{val}\""), line))
+}
+
+pub fn parse_side(val: &str) -> Option<Side> {
+    match val.to_lowercase().as_str() {
+        "top" | "toplayer" | "t" | "f.cu" => Some(Side::Top),
+        "bottom" | "bottomlayer" | "b" | "b.cu" => Some(Side::Bottom),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, serde::Deserialize)]
+    struct Entry {
+        #[serde(rename = "Key")]
+        key: String,
+
+        #[serde(rename = "Value")]
+        value: u8,
+    }
+
+    #[test]
+    fn csv_lines_after_blank_lines() {
+        let text = "Key,Value\r\n\r\nA,1\r\n\r\n\r\nB,2\r\n";
+        let records: Vec<(usize, Entry)> = parse_csv(text, "invalid-test").unwrap();
+        let lines: Vec<(usize, &str, u8)> = records
+            .iter()
+            .map(|(line, entry)| (*line, entry.key.as_str(), entry.value))
+            .collect();
+        assert_eq!(lines, [(3, "A", 1), (6, "B", 2)]);
+    }
+
+    #[test]
+    fn typed_csv_error() {
+        let err = parse_csv::<Entry>("Key,Value\n\nA,x\n", "invalid-test").unwrap_err();
+        let diagnostic = Diagnostic::from(in_file("test.csv", err));
+        let message = diagnostic.to_string();
+        assert!(
+            message.starts_with("Error[invalid-test]: test.csv:3: "),
+            "{message}"
+        );
+    }
+
+    #[test]
+    fn row_lines() {
+        let rows = read_rows(&["A;B", "", "1;2"], Some(b';')).unwrap();
+        assert_eq!(
+            rows,
+            [
+                (1, vec!["A".into(), "B".into()]),
+                (3, vec!["1".into(), "2".into()])
+            ]
+        );
+        let rows = read_rows(&["A B", "", "\"1 2\" 3"], None).unwrap();
+        assert_eq!(rows[2], (3, vec!["1 2".to_string(), "3".into()]));
+    }
+
+    #[test]
+    fn unreadable_file() {
+        let diagnostic = Diagnostic::from(read_text("missing/input.csv").unwrap_err());
+        let message = diagnostic.to_string();
+        assert!(
+            message.starts_with("Error[unreadable-file]: missing/input.csv: "),
+            "{message}"
+        );
+    }
+}
@@ -0,0 +1,222 @@
+use super::input::*;
+use super::*;
+use std::collections::HashSet;
+
+fn invalid_job(message: impl Into<String>, line: usize) -> io::Error {
+    Diagnostic::error("invalid-job", message).line(line).into()
+}
+
+#[derive(Debug, Clone)]
+pub struct YY1Job {
+    pub panel: PanelConfig,
+    pub fiducial: Vec<Position>,
+    pub overall_offset: Position,
+    pub nozzle_change: Vec<NozzleChange>,
+    pub components: Vec<ComponentRecord>,
+}
+
+/// Job file header, as read back by [`YY1Job::read`].
+///
+/// A second fiducial is written only when given; missing nozzle changes are written disabled.
+pub fn header(
+    panel: &PanelConfig,
+    fiducial: &[Position],
+    overall_offset: &Position,
+    nozzle_change: &[NozzleChange],
+) -> String {
+    let first = fiducial.first().cloned().unwrap_or_default();
+    let second = fiducial
+        .get(1)
+        .map(|fid| format!("2-X,{},2-Y,{},", fid.x, fid.y))
+        .unwrap_or_default();
+    let mut nozzle_change = nozzle_change.iter().cloned();
+    format!(
+        include_str!("header.csv"),
+        panel.as_string(),
+        first.x,
+        first.y,
+        second,
+        overall_offset.x,
+        overall_offset.y,
+        nozzle_change.next().unwrap_or_default().as_string(),
+        nozzle_change.next().unwrap_or_default().as_string(),
+        nozzle_change.next().unwrap_or_default().as_string(),
+        nozzle_change.next().unwrap_or_default().as_string()
+    )
+}
+
+impl YY1Job {
+    pub fn read(path: &str) -> io::Result<Self> {
+        Self::parse(&read_text(path)?).map_err(|err| in_file(path, err))
+    }
+
+    fn parse(text: &str) -> io::Result<Self> {
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(text.as_bytes());
+        let mut job = YY1Job {
+            panel: PanelConfig::default(),
+            fiducial: vec![],
+            overall_offset: Position::zero(),
+            nozzle_change: vec![],
+            components: vec![],
+        };
+        let mut header: Option<csv::StringRecord> = None;
+
+        for record in reader.records() {
+            let record = record.map_err(|err| {
+                let line = err.position().map_or(0, |pos| line_at(text, pos.byte()));
+                invalid_job(csv_message(&err), line)
+            })?;
+            let line_no = record.position().map_or(0, |pos| line_at(text, pos.byte()));
+            let invalid = |kind: &str| invalid_job(format!("Invalid {kind}"), line_no);
+
+            if let Some(header) = &header {
+                job.components.push(
+                    record
+                        .deserialize(Some(header))
+                        .map_err(|err| invalid_job(csv_message(&err), line_no))?,
+                );
+                continue;
+            }
+
+            let fields: Vec<&str> = record.iter().collect();
+            match fields.first().copied() {
+                Some("PanelizedPCB") => {
+                    job.panel =
+                        PanelConfig::from_fields(&fields).ok_or_else(|| invalid("panel"))?;
+                }
+                Some("Fiducial") => {
+                    let value = |key: &str| {
+                        fields
+                            .iter()
+                            .position(|field| *field == key)
+                            .and_then(|idx| fields.get(idx + 1))
+                            .and_then(|val| val.parse().ok())
+                            .ok_or_else(|| invalid("fiducial"))
+                    };
+                    job.fiducial = vec![Position::new(value("1-X")?, value("1-Y")?)];
+                    if fields.contains(&"2-X") {
+                        job.fiducial
+                            .push(Position::new(value("2-X")?, value("2-Y")?));
+                    }
+                    job.overall_offset =
+                        Position::new(value("OverallOffsetX")?, value("OverallOffsetY")?);
+                }
+                Some("NozzleChange") => {
+                    job.nozzle_change.push(
+                        NozzleChange::from_fields(&fields)
+                            .ok_or_else(|| invalid("nozzle change"))?,
+                    );
+                }
+                Some("Designator") => header = Some(record),
+                _ => {}
+            }
+        }
+
+        if header.is_none() {
+            return Err(Diagnostic::error("invalid-job", "Component table not found").into());
+        }
+
+        Ok(job)
+    }
+
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = vec![];
+
+        let placeholder = ComponentRecord::placeholder();
+        let mut designators = HashSet::new();
+        for comp in &self.components {
+            if comp.reference == placeholder.reference {
+                continue;
+            }
+            if !designators.insert(&comp.reference) {
+                problems.push(format!("Duplicate designator: {}", comp.reference));
+            }
+            if comp.skip == 0 && comp.feeder == 0 {
+                problems.push(format!("Unknown feeder for component: {}", comp.reference));
+            }
+        }
+
+        for nozzle_change in self.nozzle_change.iter().filter(|change| change.enabled) {
+            if nozzle_change.before_component > self.components.len() {
+                problems.push(format!(
+                    "Nozzle change before missing component #{}",
+                    nozzle_change.before_component
+                ));
+            }
+        }
+
+        problems
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Job file as written by the converter, with the given header and one component.
+    fn job_file(header: String) -> String {
+        let mut writer = csv::Writer::from_writer(header.into_bytes());
+        writer.serialize(ComponentRecord::placeholder()).unwrap();
+        String::from_utf8(writer.into_inner().unwrap()).unwrap()
+    }
+
+    fn nozzle_change(before_component: usize, head: Head) -> NozzleChange {
+        NozzleChange {
+            enabled: true,
+            before_component,
+            head,
+            drop_station: NozzleStation::Station1,
+            pickup_station: NozzleStation::Station2,
+        }
+    }
+
+    #[test]
+    fn header_round_trip() {
+        let panel = PanelConfig::new(false, 2, 3, Size::new(50.5, 30.0));
+        let fiducial = [Position::new(1.5, 2.0), Position::new(48.0, -3.25)];
+        let overall_offset = Position::new(10.0, 20.0);
+        let changes = [nozzle_change(3, Head::Head1), nozzle_change(7, Head::Head2)];
+        let text = job_file(header(&panel, &fiducial, &overall_offset, &changes));
+
+        let job = YY1Job::parse(&text).unwrap();
+        assert_eq!(job.panel, panel);
+        assert_eq!(job.fiducial, fiducial);
+        assert_eq!(job.overall_offset, overall_offset);
+        assert_eq!(job.nozzle_change[..2], changes);
+        assert_eq!(job.nozzle_change[2..], [NozzleChange::default(); 2]);
+        assert_eq!(job.components.len(), 1);
+
+        let text = job_file(header(&panel, &fiducial[..1], &overall_offset, &[]));
+        let job = YY1Job::parse(&text).unwrap();
+        assert_eq!(job.fiducial, fiducial[..1]);
+        assert_eq!(job.nozzle_change, [NozzleChange::default(); 4]);
+    }
+
+    #[test]
+    fn malformed_lines() {
+        let panel = PanelConfig::default();
+        let fiducial = [Position::zero()];
+        let text = job_file(header(&panel, &fiducial, &Position::zero(), &[]));
+        let error = |from: &str, to: &str| {
+            let text = text.replacen(from, to, 1);
+            in_file("job.csv", YY1Job::parse(&text).unwrap_err()).to_string()
+        };
+
+        assert_eq!(
+            error("Rows,1", "Rows,x"),
+            "Error[invalid-job]: job.csv:3: Invalid panel"
+        );
+        assert_eq!(
+            error("1-Y,0", "1-Y,"),
+            "Error[invalid-job]: job.csv:5: Invalid fiducial"
+        );
+        assert_eq!(
+            error("NozzleChange,OFF", "NozzleChange,MAYBE"),
+            "Error[invalid-job]: job.csv:7: Invalid nozzle change"
+        );
+        assert!(error("Designator,", "Reference,").contains("Component table not found"));
+    }
+}
@@ -0,0 +1,254 @@
+use super::input::*;
+use super::sexpr::Sexpr;
+use super::*;
+
+pub fn read_pos(text: &str) -> io::Result<Vec<KiCadRecord>> {
+    let mut units = Units::Millimeters;
+    let mut columns: Vec<String> = vec![];
+    let mut records = vec![];
+
+    for (idx, line) in text.lines().enumerate() {
+        let line_no = idx + 1;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(comment) = line.strip_prefix('#') {
+            let comment = comment.trim_start_matches('#').trim();
+            if let Some(unit) = comment.strip_prefix("Unit =") {
+                units = unit
+                    .split(',')
+                    .next()
+                    .unwrap_or_default()
+                    .trim()
+                    .parse()
+                    .map_err(|err: String| invalid_input_at(err, line_no))?;
+            } else if comment.starts_with("Ref") {
+                columns = split_fields(comment);
+            }
+            continue;
+        }
+
+        let fields = split_fields(line);
+        let field = |name: &str| {
+            columns
+                .iter()
+                .position(|column| column == name)
+                .and_then(|idx| fields.get(idx))
+                .ok_or_else(|| invalid_input_at(format!("Missing {name} column"), line_no))
+        };
+        let side_name = field("Side")?;
+        let side = parse_side(side_name)
+            .ok_or_else(|| invalid_input_at(format!("Invalid side \"{side_name}\""), line_no))?;
+        records.push(KiCadRecord {
+            reference: field("Ref")?.clone(),
+            value: field("Val")?.clone(),
+            package: field("Package")?.clone(),
+            position_x: parse_length(field("PosX")?, units, line_no)?,
+            position_y: parse_length(field("PosY")?, units, line_no)?,
+            rotation: parse_number(field("Rot")?, line_no)?,
+            side,
+            ..Default::default()
+        });
+    }
+
+    Ok(records)
+}
+
+pub fn read_pcb(text: &str) -> io::Result<Placement> {
+    let board = Sexpr::parse(text)?;
+    let outline = board_outline(&board);
+    let (origin_x, origin_y) = board
+        .child("setup")
+        .and_then(|setup| setup.child("aux_axis_origin"))
+        .and_then(point)
+        .filter(|origin| *origin != (0.0, 0.0))
+        .or(outline.map(|((min_x, _), (_, max_y))| (min_x, max_y)))
+        .unwrap_or_default();
+
+    let mut records = vec![];
+    for footprint in board.children("footprint").chain(board.children("module")) {
+        let attributes: Vec<&str> = footprint
+            .child("attr")
+            .map(|attr| attr.args().collect())
+            .unwrap_or_default();
+        if attributes
+            .iter()
+            .any(|attr| *attr == "exclude_from_pos_files" || *attr == "virtual")
+        {
+            continue;
+        }
+
+        let reference = footprint_field(footprint, "Reference").unwrap_or_default();
+        let invalid = |message: &str| -> io::Error {
+            Diagnostic::error("invalid-input", message)
+                .designator(&reference)
+                .into()
+        };
+        let side = match footprint.child("layer").and_then(|layer| layer.arg(0)) {
+            Some("F.Cu") => Side::Top,
+            Some("B.Cu") => Side::Bottom,
+            _ => return Err(invalid("Invalid footprint layer")),
+        };
+        let at = footprint
+            .child("at")
+            .ok_or_else(|| invalid("Missing footprint position"))?;
+        let (x, y) = point(at).ok_or_else(|| invalid("Invalid footprint position"))?;
+        let rotation = at.arg(2).and_then(|rot| rot.parse().ok()).unwrap_or(0.0);
+        let package = footprint.arg(0).unwrap_or_default();
+        let package = package.split_once(':').map_or(package, |(_, name)| name);
+
+        records.push(KiCadRecord {
+            reference,
+            value: footprint_field(footprint, "Value").unwrap_or_default(),
+            package: package.into(),
+            position_x: (x - origin_x) as f32,
+            position_y: (origin_y - y) as f32,
+            rotation,
+            side,
+            dnp: attributes.contains(&"dnp"),
+            fields: footprint
+                .children("property")
+                .filter_map(|prop| Some((prop.arg(0)?.into(), prop.arg(1)?.into())))
+                .collect(),
+            ..Default::default()
+        });
+    }
+
+    Ok(Placement {
+        records,
+        board_width: outline.map(|((min_x, _), (max_x, _))| (max_x - min_x) as f32),
+    })
+}
+
+fn point(expr: &Sexpr) -> Option<(f64, f64)> {
+    let x = expr.arg(0)?.parse().ok()?;
+    let y = expr.arg(1)?.parse().ok()?;
+    Some((x, y))
+}
+
+fn footprint_field(footprint: &Sexpr, name: &str) -> Option<String> {
+    footprint
+        .children("property")
+        .find(|prop| prop.arg(0) == Some(name))
+        .and_then(|prop| prop.arg(1))
+        .or_else(|| {
+            let kind = name.to_lowercase();
+            footprint
+                .children("fp_text")
+                .find(|text| text.arg(0) == Some(kind.as_str()))
+                .and_then(|text| text.arg(1))
+        })
+        .map(String::from)
+}
+
+fn board_outline(board: &Sexpr) -> Option<((f64, f64), (f64, f64))> {
+    let Sexpr::List(items) = board else {
+        return None;
+    };
+    let points: Vec<(f64, f64)> = items
+        .iter()
+        .filter(|item| {
+            item.name().is_some_and(|name| name.starts_with("gr_"))
+                && item.child("layer").and_then(|layer| layer.arg(0)) == Some("Edge.Cuts")
+        })
+        .flat_map(|item| {
+            let vertices = item
+                .child("pts")
+                .into_iter()
+                .flat_map(|pts| pts.children("xy"));
+            ["start", "mid", "end"]
+                .into_iter()
+                .filter_map(|name| item.child(name))
+                .chain(vertices)
+                .filter_map(point)
+        })
+        .collect();
+
+    let first = *points.first()?;
+    Some(points.iter().fold((first, first), |(min, max), (x, y)| {
+        (
+            (min.0.min(*x), min.1.min(*y)),
+            (max.0.max(*x), max.1.max(*y)),
+        )
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_ascii_pos() {
+        let text = "### Footprint positions - created on 2024-01-01 ###
+## Unit = inches, Angle = deg.
+## Side : All
+# Ref     Val       Package                PosX       PosY       Rot  Side
+C1        100n      C_0402_1005Metric      1.0000    -0.5000   90.0000  top
+J1        \"Conn 2\"  PinHeader_1x02         0.2500    -0.1000  180.0000  bottom
+## End
+";
+        let records = read_pos(text).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].reference, "C1");
+        assert_eq!(records[0].package, "C_0402_1005Metric");
+        assert!((records[0].position_x - 25.4).abs() < 1e-4);
+        assert!((records[0].position_y + 12.7).abs() < 1e-4);
+        assert_eq!(records[0].rotation, 90.0);
+        assert_eq!(records[0].side, Side::Top);
+        assert_eq!(records[1].value, "Conn 2");
+        assert_eq!(records[1].side, Side::Bottom);
+    }
+
+    #[test]
+    fn reject_invalid_pos_side() {
+        let text = "# Ref Val Package PosX PosY Rot Side\nC1 100n C0402 1 2 0 inner\n";
+        assert!(read_pos(text).is_err());
+    }
+
+    #[test]
+    fn read_board_footprints() {
+        let text = r#"(kicad_pcb (version 20240108)
+  (setup (aux_axis_origin 0 0))
+  (gr_rect (start 100 50) (end 150 80) (layer "Edge.Cuts"))
+  (footprint "Resistor_SMD:R_0603_1608Metric" (layer "F.Cu") (at 110 60 90)
+    (property "Reference" "R1")
+    (property "Value" "10k")
+    (property "MPN" "RC0603FR-0710KL")
+    (attr smd dnp))
+  (footprint "TestPoint:TestPoint_Pad_1.0x1.0mm" (layer "F.Cu") (at 120 70)
+    (property "Reference" "TP1")
+    (attr exclude_from_pos_files))
+  (module Package_SO:SOIC-8 (layer B.Cu) (at 140 75 180)
+    (fp_text reference U1 (at 0 0))
+    (fp_text value "LM358" (at 0 1)))
+)"#;
+        let placement = read_pcb(text).unwrap();
+        assert_eq!(placement.board_width, Some(50.0));
+        let records = placement.records;
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].reference, "R1");
+        assert_eq!(records[0].value, "10k");
+        assert_eq!(records[0].package, "R_0603_1608Metric");
+        assert_eq!((records[0].position_x, records[0].position_y), (10.0, 20.0));
+        assert_eq!(records[0].rotation, 90.0);
+        assert!(records[0].dnp);
+        assert_eq!(records[0].fields["MPN"], "RC0603FR-0710KL");
+        assert_eq!(records[1].reference, "U1");
+        assert_eq!(records[1].value, "LM358");
+        assert_eq!(records[1].side, Side::Bottom);
+        assert_eq!((records[1].position_x, records[1].position_y), (40.0, 5.0));
+    }
+
+    #[test]
+    fn board_aux_origin() {
+        let text = r#"(kicad_pcb
+  (setup (aux_axis_origin 105 85))
+  (footprint "C_0402" (layer "F.Cu") (at 110 60) (property "Reference" "C1")))"#;
+        let placement = read_pcb(text).unwrap();
+        assert_eq!(placement.board_width, None);
+        let record = &placement.records[0];
+        assert_eq!((record.position_x, record.position_y), (5.0, 25.0));
+    }
+}
@@ -0,0 +1,165 @@
+use super::input::*;
+use super::*;
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct LibraryPart {
+    #[serde(rename = "MPN")]
+    mpn: String,
+
+    #[serde(rename = "Package", default)]
+    package: String,
+
+    #[serde(rename = "Value", default)]
+    value: String,
+
+    #[serde(rename = "Nozzle")]
+    nozzle: Nozzle,
+
+    #[serde(rename = "PickHeight", default)]
+    pick_height: f32,
+
+    #[serde(rename = "PlaceHeight", default)]
+    place_height: f32,
+
+    #[serde(rename = "MountSpeed", default = "default_mount_speed")]
+    mount_speed: u8,
+
+    #[serde(rename = "Mode", default)]
+    mode: u8,
+
+    #[serde(rename = "Rotation", default)]
+    rotation: f32,
+
+    #[serde(rename = "TapeWidth", default)]
+    tape_width: Option<f32>,
+
+    #[serde(rename = "TapePitch", default)]
+    tape_pitch: Option<f32>,
+}
+
+/// Part physics keyed by manufacturer part number.
+#[derive(Debug, Default)]
+pub struct PartLibrary {
+    parts: HashMap<String, LibraryPart>,
+}
+
+impl PartLibrary {
+    pub fn load(path: &str) -> io::Result<Self> {
+        Self::parse(&read_text(path)?).map_err(|err| in_file(path, err))
+    }
+
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let parts = parse_csv::<LibraryPart>(text, "invalid-library")?
+            .into_iter()
+            .map(|(_, part)| (part.mpn.clone(), part))
+            .collect();
+        Ok(Self { parts })
+    }
+
+    pub fn contains(&self, mpn: &str) -> bool {
+        self.parts.contains_key(mpn)
+    }
+
+    /// Fills a feeder slot (`Feeder`, `Part`) with the part settings from the library.
+    ///
+    /// The slot keeps its feeder number, skip flag and pocket layout. `Rotation`, `PickHeight`
+    /// and `PlaceHeight` set (non-zero) on the slot win over the library, as they depend on how
+    /// the part sits in that feeder. Cut-tape strips default to the tape pitch.
+    pub fn resolve(&self, slot: FeederConfig, diagnostics: &mut Diagnostics) -> FeederConfig {
+        let Some(part) = self.parts.get(&slot.part) else {
+            return slot;
+        };
+        let tape = match (part.tape_width, part.tape_pitch) {
+            (Some(width), Some(pitch)) => format!(" ({width}mm tape, {pitch}mm pitch)"),
+            (Some(width), None) => format!(" ({width}mm tape)"),
+            _ => String::new(),
+        };
+        diagnostics.push(Diagnostic::info(
+            "feeder-slot",
+            format!("Feeder #{}: {}{tape}.", slot.feeder, part.mpn),
+        ));
+
+        let slot_or_part = |slot: f32, part: f32| if slot != 0.0 { slot } else { part };
+        FeederConfig {
+            package: part.package.clone(),
+            value: part.value.clone(),
+            rotation: slot_or_part(slot.rotation, part.rotation),
+            pick_height: slot_or_part(slot.pick_height, part.pick_height),
+            place_height: slot_or_part(slot.place_height, part.place_height),
+            mount_speed: part.mount_speed,
+            nozzle: Some(part.nozzle),
+            mode: part.mode,
+            pitch_x: slot
+                .pitch_x
+                .or(part.tape_pitch.filter(|_| slot.pick_x.is_some())),
+            ..slot
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIBRARY: &str =
+        "MPN,Package,Value,Nozzle,PickHeight,PlaceHeight,Rotation,TapeWidth,TapePitch
+GRM155R71C104KA88D,0402,100nF,CN040,0.5,0.6,90,8,2
+LM358DR,SOIC-8,LM358,CN140,1.5,1.75,0,12,
+";
+
+    fn slot(text: &str) -> FeederConfig {
+        let mut reader = csv::Reader::from_reader(text.as_bytes());
+        reader.deserialize().next().unwrap().unwrap()
+    }
+
+    fn resolve(slot: FeederConfig) -> FeederConfig {
+        let library = PartLibrary::parse(LIBRARY).unwrap();
+        let mut diagnostics = Diagnostics::new(OutputFormat::Json, false);
+        library.resolve(slot, &mut diagnostics)
+    }
+
+    #[test]
+    fn resolve_library_part() {
+        let cfg = resolve(slot("Feeder,Part,Skip\n4,GRM155R71C104KA88D,1\n"));
+        assert_eq!((cfg.feeder, cfg.skip), (4, 1));
+        assert_eq!(
+            (cfg.value.as_str(), cfg.package.as_str()),
+            ("100nF", "0402")
+        );
+        assert_eq!(cfg.nozzle, Some(Nozzle::CN040));
+        assert_eq!(
+            (cfg.pick_height, cfg.place_height, cfg.rotation),
+            (0.5, 0.6, 90.0)
+        );
+        assert_eq!(cfg.pitch_x, None);
+
+        let cfg = resolve(slot("Feeder,Part,Value\n5,C1525,100nF\n"));
+        assert_eq!(cfg.nozzle, None);
+        assert_eq!(cfg.value, "100nF");
+    }
+
+    #[test]
+    fn slot_settings_win() {
+        let cfg = resolve(slot(
+            "Feeder,Part,Rotation,PickHeight,PlaceHeight\n4,GRM155R71C104KA88D,180,0,0.8\n",
+        ));
+        assert_eq!(
+            (cfg.pick_height, cfg.place_height, cfg.rotation),
+            (0.5, 0.8, 180.0)
+        );
+    }
+
+    #[test]
+    fn strip_pitch_from_tape() {
+        let cfg = resolve(slot(
+            "Feeder,Part,PickX,PickY\n30,GRM155R71C104KA88D,10,20\n",
+        ));
+        assert_eq!(cfg.pitch_x, Some(2.0));
+        let cfg = resolve(slot(
+            "Feeder,Part,PickX,PickY,PitchX\n30,GRM155R71C104KA88D,10,20,4\n",
+        ));
+        assert_eq!(cfg.pitch_x, Some(4.0));
+        let cfg = resolve(slot("Feeder,Part,PickX,PickY\n31,LM358DR,10,20\n"));
+        assert_eq!(cfg.pitch_x, None);
+    }
+}
@@ -0,0 +1,364 @@
+use super::input::*;
+use super::*;
+use std::ops::RangeInclusive;
+
+#[derive(Debug, serde::Deserialize)]
+struct ProfileEntry {
+    #[serde(rename = "Key")]
+    key: String,
+
+    #[serde(rename = "Value")]
+    value: String,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum HeaderVariant {
+    #[default]
+    SingleFiducial,
+    TwoFiducials,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FeederKind {
+    Tape,
+    Strip,
+    Vibration,
+    Tray,
+}
+
+#[derive(Clone, Debug)]
+pub struct MachineProfile {
+    work_area: Option<Size>,
+    placeholder: Position,
+    placeholder_feeder: u8,
+    placeholder_height: f32,
+    stations: u8,
+    feeders: Vec<(FeederKind, RangeInclusive<u8>)>,
+    header: HeaderVariant,
+}
+
+impl Default for MachineProfile {
+    fn default() -> Self {
+        let placeholder = ComponentRecord::placeholder();
+        Self {
+            work_area: None,
+            placeholder: Position::new(placeholder.position_x, placeholder.position_y),
+            placeholder_feeder: placeholder.feeder,
+            placeholder_height: placeholder.place_height,
+            stations: 3,
+            feeders: vec![
+                (FeederKind::Tape, 1..=29),
+                (FeederKind::Strip, 30..=60),
+                (FeederKind::Vibration, 61..=66),
+                (FeederKind::Tray, 67..=79),
+            ],
+            header: HeaderVariant::SingleFiducial,
+        }
+    }
+}
+
+impl MachineProfile {
+    pub fn load(path: &str) -> io::Result<Self> {
+        Self::parse(&read_text(path)?).map_err(|err| in_file(path, err))
+    }
+
+    fn parse(text: &str) -> io::Result<Self> {
+        let mut profile = Self::default();
+        let mut work_area = Size::zero();
+        let mut feeders = vec![];
+        for (line, entry) in parse_csv::<ProfileEntry>(text, "invalid-profile")? {
+            let error = |message: String| Diagnostic::error("invalid-profile", message).line(line);
+            let invalid = || error(format!("Invalid machine {}", entry.key));
+            let length = || {
+                Units::Millimeters
+                    .parse_length(&entry.value)
+                    .ok_or_else(invalid)
+            };
+            match entry.key.as_str() {
+                "WorkAreaX" => work_area.width = length()?,
+                "WorkAreaY" => work_area.height = length()?,
+                "PlaceholderX" => profile.placeholder.x = length()?,
+                "PlaceholderY" => profile.placeholder.y = length()?,
+                "PlaceholderHeight" => profile.placeholder_height = length()?,
+                "PlaceholderFeeder" => {
+                    profile.placeholder_feeder = entry.value.parse().map_err(|_| invalid())?
+                }
+                "Stations" => {
+                    profile.stations = match entry.value.parse() {
+                        Ok(stations @ 1..=3) => stations,
+                        _ => return Err(invalid().into()),
+                    }
+                }
+                "TapeFeeders" | "StripFeeders" | "VibrationFeeders" | "TrayFeeders" => {
+                    let kind = match entry.key.as_str() {
+                        "TapeFeeders" => FeederKind::Tape,
+                        "StripFeeders" => FeederKind::Strip,
+                        "VibrationFeeders" => FeederKind::Vibration,
+                        _ => FeederKind::Tray,
+                    };
+                    let ranges = parse_ranges(&entry.value).ok_or_else(invalid)?;
+                    feeders.extend(ranges.into_iter().map(|range| (kind, range)));
+                }
+                "Header" => {
+                    profile.header = match entry.value.as_str() {
+                        "SingleFiducial" => HeaderVariant::SingleFiducial,
+                        "TwoFiducials" => HeaderVariant::TwoFiducials,
+                        _ => return Err(invalid().into()),
+                    }
+                }
+                key => return Err(error(format!("Unknown machine key: {key}")).into()),
+            }
+        }
+        if work_area.width > 0.0 && work_area.height > 0.0 {
+            profile.work_area = Some(work_area);
+        }
+        if !feeders.is_empty() {
+            profile.feeders = feeders;
+        }
+        Ok(profile)
+    }
+
+    pub fn placeholder(&self) -> ComponentRecord {
+        ComponentRecord {
+            position_x: self.placeholder.x,
+            position_y: self.placeholder.y,
+            feeder: self.placeholder_feeder,
+            pick_height: self.placeholder_height,
+            place_height: self.placeholder_height,
+            ..ComponentRecord::placeholder()
+        }
+    }
+
+    pub fn stations(&self) -> u8 {
+        self.stations
+    }
+
+    pub fn header(&self) -> HeaderVariant {
+        self.header
+    }
+
+    /// Returns the kind of the feeder slot, or `None` if the slot does not exist.
+    pub fn feeder_kind(&self, feeder: u8) -> Option<FeederKind> {
+        self.feeders
+            .iter()
+            .find(|(_, range)| range.contains(&feeder))
+            .map(|(kind, _)| *kind)
+    }
+
+    /// Checks the feeder config (read from `path`) against the machine feeder layout.
+    pub fn validate_feeders(&self, feeders: &[FeederConfig], path: &str) -> io::Result<()> {
+        let mut slots: HashMap<u8, &FeederConfig> = HashMap::new();
+        for feeder in feeders.iter().filter(|feeder| feeder.feeder != 0) {
+            let error = |code: &'static str, message: String| -> io::Result<()> {
+                Err(Diagnostic::error(code, message)
+                    .file(path)
+                    .line(feeder.line)
+                    .into())
+            };
+            if self.feeder_kind(feeder.feeder).is_none() {
+                return error(
+                    "feeder-not-on-machine",
+                    format!(
+                        "Feeder #{} does not exist on this machine. Component: {} - {}.",
+                        feeder.feeder, feeder.value, feeder.package
+                    ),
+                );
+            }
+            if feeder.pockets().is_some()
+                && !matches!(
+                    self.feeder_kind(feeder.feeder),
+                    Some(FeederKind::Tray | FeederKind::Strip)
+                )
+            {
+                return error(
+                    "feeder-kind",
+                    format!(
+                        "Feeder #{} is not a tray or strip position. Component: {} - {}.",
+                        feeder.feeder, feeder.value, feeder.package
+                    ),
+                );
+            }
+            if let Some(other) = slots.insert(feeder.feeder, feeder) {
+                let same_part = if feeder.part.is_empty() && other.part.is_empty() {
+                    feeder.value == other.value && feeder.package == other.package
+                } else {
+                    feeder.part == other.part
+                };
+                if !same_part {
+                    return error(
+                        "feeder-conflict",
+                        format!(
+                            "Feeder #{} is assigned to different parts: {} - {} and {} - {}.",
+                            feeder.feeder, other.value, other.package, feeder.value, feeder.package
+                        ),
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn contains(&self, comp: &ComponentRecord) -> bool {
+        match &self.work_area {
+            Some(area) => {
+                (0.0..=area.width).contains(&comp.position_x)
+                    && (0.0..=area.height).contains(&comp.position_y)
+            }
+            None => true,
+        }
+    }
+}
+
+/// Parses feeder ranges like `1-28|29|60-63`.
+fn parse_ranges(val: &str) -> Option<Vec<RangeInclusive<u8>>> {
+    val.split('|')
+        .map(|range| match range.split_once('-') {
+            Some((start, end)) => Some(start.trim().parse().ok()?..=end.trim().parse().ok()?),
+            None => {
+                let feeder = range.trim().parse().ok()?;
+                Some(feeder..=feeder)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feeders(text: &str) -> Vec<FeederConfig> {
+        parse_csv::<FeederConfig>(text, "invalid-feeder")
+            .unwrap()
+            .into_iter()
+            .map(|(line, cfg)| FeederConfig { line, ..cfg })
+            .collect()
+    }
+
+    fn validate(text: &str) -> Result<(), String> {
+        MachineProfile::default()
+            .validate_feeders(&feeders(text), "feeders.csv")
+            .map_err(|err| Diagnostic::from(err).to_string())
+    }
+
+    fn parse_error(text: &str) -> String {
+        Diagnostic::from(MachineProfile::parse(text).unwrap_err()).to_string()
+    }
+
+    #[test]
+    fn parse_profile() {
+        let profile = MachineProfile::parse(
+            "Key,Value\nWorkAreaX,100\nWorkAreaY,80\nPlaceholderX,90\nPlaceholderY,70\n\
+             PlaceholderFeeder,29\nPlaceholderHeight,12.5\nStations,2\n\
+             TapeFeeders,1-10|12\nTrayFeeders,20-21\nHeader,TwoFiducials\n",
+        )
+        .unwrap();
+        let placeholder = profile.placeholder();
+        assert_eq!(
+            (placeholder.position_x, placeholder.position_y),
+            (90.0, 70.0)
+        );
+        assert_eq!(placeholder.feeder, 29);
+        assert_eq!(
+            (placeholder.pick_height, placeholder.place_height),
+            (12.5, 12.5)
+        );
+        assert_eq!(profile.stations(), 2);
+        assert_eq!(profile.header(), HeaderVariant::TwoFiducials);
+        assert_eq!(profile.feeder_kind(12), Some(FeederKind::Tape));
+        assert_eq!(profile.feeder_kind(11), None);
+        assert_eq!(profile.feeder_kind(21), Some(FeederKind::Tray));
+        assert_eq!(profile.feeder_kind(30), None);
+        let inside = ComponentRecord {
+            position_x: 100.0,
+            position_y: 0.0,
+            ..ComponentRecord::placeholder()
+        };
+        assert!(profile.contains(&inside));
+        assert!(!profile.contains(&ComponentRecord {
+            position_y: -0.1,
+            ..inside
+        }));
+    }
+
+    #[test]
+    fn example_profile() {
+        let profile = MachineProfile::parse(include_str!("../../example/machine_profile.csv"));
+        let profile = profile.unwrap();
+        let stock = MachineProfile::default();
+        assert_eq!(profile.feeders, stock.feeders);
+        assert_eq!(profile.stations(), stock.stations());
+        assert_eq!(profile.header(), stock.header());
+    }
+
+    #[test]
+    fn invalid_profile() {
+        assert_eq!(
+            parse_error("Key,Value\nStations,2\nNozzles,4\n"),
+            "Error[invalid-profile]: Unknown machine key: Nozzles"
+        );
+        assert_eq!(
+            parse_error("Key,Value\nStations,0\n"),
+            "Error[invalid-profile]: Invalid machine Stations"
+        );
+        assert_eq!(
+            parse_error("Key,Value\nStations,4\n"),
+            "Error[invalid-profile]: Invalid machine Stations"
+        );
+        assert_eq!(
+            parse_error("Key,Value\nTapeFeeders,1-x\n"),
+            "Error[invalid-profile]: Invalid machine TapeFeeders"
+        );
+        assert_eq!(
+            parse_error("Key,Value\nHeader,ThreeFiducials\n"),
+            "Error[invalid-profile]: Invalid machine Header"
+        );
+        let err = MachineProfile::parse("Key,Value\n\nPlaceholderX,far\n").unwrap_err();
+        assert_eq!(
+            in_file("machine.csv", err).to_string(),
+            "Error[invalid-profile]: machine.csv:3: Invalid machine PlaceholderX"
+        );
+    }
+
+    #[test]
+    fn stock_feeder_layout() {
+        let profile = MachineProfile::default();
+        assert_eq!(profile.feeder_kind(1), Some(FeederKind::Tape));
+        assert_eq!(profile.feeder_kind(30), Some(FeederKind::Strip));
+        assert_eq!(profile.feeder_kind(66), Some(FeederKind::Vibration));
+        assert_eq!(profile.feeder_kind(79), Some(FeederKind::Tray));
+        assert_eq!(profile.feeder_kind(80), None);
+    }
+
+    #[test]
+    fn missing_slot() {
+        assert_eq!(
+            validate("Feeder,Value,Package\n0,DNP,0402\n80,100nF,0402\n"),
+            Err("Error[feeder-not-on-machine]: feeders.csv:3: \
+                 Feeder #80 does not exist on this machine. Component: 100nF - 0402."
+                .into())
+        );
+    }
+
+    #[test]
+    fn pockets_need_tray_or_strip() {
+        assert!(validate("Feeder,Value,Package,PickX,PickY\n67,STM32,LQFP48,10,20\n").is_ok());
+        assert_eq!(
+            validate("Feeder,Value,Package,PickX,PickY\n5,STM32,LQFP48,10,20\n"),
+            Err("Error[feeder-kind]: feeders.csv:2: \
+                 Feeder #5 is not a tray or strip position. Component: STM32 - LQFP48."
+                .into())
+        );
+    }
+
+    #[test]
+    fn conflicting_parts() {
+        assert!(validate("Feeder,Value,Package,Part\n3,10k,0402,R10K\n3,10K,0402,R10K\n").is_ok());
+        assert!(validate("Feeder,Value,Package\n3,10k,0402\n3,10k,0402\n").is_ok());
+        assert_eq!(
+            validate("Feeder,Value,Package,Part\n3,10k,0402,R10K\n3,1k,0402,R1K\n"),
+            Err("Error[feeder-conflict]: feeders.csv:3: \
+                 Feeder #3 is assigned to different parts: 10k - 0402 and 1k - 0402."
+                .into())
+        );
+    }
+}
@@ -0,0 +1,244 @@
+use super::*;
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+/// Component left for hand assembly.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ManualRecord {
+    #[serde(rename = "Part")]
+    part: String,
+
+    #[serde(rename = "Designator")]
+    reference: String,
+
+    #[serde(rename = "Comment")]
+    value: String,
+
+    #[serde(rename = "Footprint")]
+    package: String,
+
+    #[serde(rename = "Mid X(mm)")]
+    position_x: f32,
+
+    #[serde(rename = "Mid Y(mm)")]
+    position_y: f32,
+
+    #[serde(rename = "Rotation")]
+    rotation: f32,
+
+    #[serde(rename = "Side")]
+    side: String,
+
+    #[serde(rename = "Reason")]
+    reason: String,
+}
+
+impl ManualRecord {
+    pub fn new(comp: &ComponentRecord, reason: String) -> Self {
+        let part = [comp.part.as_str(), comp.mpn()]
+            .into_iter()
+            .find(|part| !part.is_empty())
+            .unwrap_or_default();
+        Self {
+            part: part.into(),
+            reference: comp.reference.clone(),
+            value: comp.value.clone(),
+            package: comp.package.clone(),
+            position_x: comp.position_x,
+            position_y: comp.position_y,
+            rotation: comp.rotation,
+            side: comp.side.as_str().into(),
+            reason,
+        }
+    }
+
+    fn group(&self) -> (&str, &str, &str) {
+        (&self.value, &self.package, &self.part)
+    }
+}
+
+/// Manual assembly checklist of everything the machine won't place, grouped by part.
+#[derive(Debug, Default)]
+pub struct ManualSheet {
+    records: Vec<ManualRecord>,
+}
+
+impl ManualSheet {
+    pub fn push(&mut self, comp: &ComponentRecord, reason: String) {
+        self.records.push(ManualRecord::new(comp, reason));
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    fn groups(&self) -> Vec<Vec<&ManualRecord>> {
+        let mut records: Vec<&ManualRecord> = self.records.iter().collect();
+        records.sort_by(|a, b| {
+            a.group()
+                .cmp(&b.group())
+                .then_with(|| compare_references(&a.reference, &b.reference))
+        });
+        records
+            .chunk_by(|a, b| a.group() == b.group())
+            .map(<[&ManualRecord]>::to_vec)
+            .collect()
+    }
+
+    /// Writes `<output>_manual.csv` and `<output>_manual.html`, returning the CSV path.
+    pub fn write(&self, output_path: &Path) -> io::Result<String> {
+        let file_name = output_path
+            .file_stem()
+            .map(|step| format!("{}_manual", step.to_string_lossy()))
+            .unwrap();
+        let csv_path = output_path.with_file_name(&file_name).with_extension("csv");
+        let html_path = output_path
+            .with_file_name(&file_name)
+            .with_extension("html");
+
+        let groups = self.groups();
+        let mut writer = csv::WriterBuilder::default()
+            .terminator(csv::Terminator::CRLF)
+            .from_path(&csv_path)?;
+        for record in groups.iter().flatten() {
+            writer.serialize(record)?;
+        }
+        writer.flush()?;
+
+        let mut html = File::create(html_path)?;
+        writeln!(html, "<!DOCTYPE html>")?;
+        writeln!(html, "<html>\n<head>\n<meta charset=\"utf-8\">")?;
+        writeln!(
+            html,
+            "<title>Manual assembly - {}</title>",
+            escape(&file_name)
+        )?;
+        writeln!(
+            html,
+            "<style>body{{font-family:sans-serif}}table{{border-collapse:collapse;margin-bottom:1em}}\
+             th,td{{border:1px solid #999;padding:2px 8px;text-align:left}}</style>"
+        )?;
+        writeln!(html, "</head>\n<body>")?;
+        writeln!(html, "<h1>Manual assembly ({} components)</h1>", self.len())?;
+        for group in groups {
+            let first = group[0];
+            let part = if first.part.is_empty() {
+                String::new()
+            } else {
+                format!(" ({})", escape(&first.part))
+            };
+            writeln!(
+                html,
+                "<h2>{} - {}{part} &times; {}</h2>",
+                escape(&first.value),
+                escape(&first.package),
+                group.len()
+            )?;
+            writeln!(
+                html,
+                "<table>\n<tr><th>Done</th><th>Designator</th><th>X (mm)</th><th>Y (mm)</th>\
+                 <th>Rotation</th><th>Side</th><th>Reason</th></tr>"
+            )?;
+            for record in group {
+                writeln!(
+                    html,
+                    "<tr><td><input type=\"checkbox\"></td><td>{}</td><td>{}</td><td>{}</td>\
+                     <td>{}</td><td>{}</td><td>{}</td></tr>",
+                    escape(&record.reference),
+                    record.position_x,
+                    record.position_y,
+                    record.rotation,
+                    record.side,
+                    escape(&record.reason)
+                )?;
+            }
+            writeln!(html, "</table>")?;
+        }
+        writeln!(html, "</body>\n</html>")?;
+
+        Ok(csv_path.to_string_lossy().into())
+    }
+}
+
+/// Orders designators naturally, so `C2` comes before `C10`.
+fn compare_references(a: &str, b: &str) -> Ordering {
+    let split = |reference: &str| {
+        let idx = reference
+            .find(|ch: char| ch.is_ascii_digit())
+            .unwrap_or(reference.len());
+        let (prefix, rest) = reference.split_at(idx);
+        let digits = rest
+            .find(|ch: char| !ch.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let number: u64 = rest[..digits].parse().unwrap_or_default();
+        (prefix.to_string(), number, rest[digits..].to_string())
+    };
+    split(a).cmp(&split(b))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn component(reference: &str, value: &str, package: &str) -> ComponentRecord {
+        ComponentRecord {
+            reference: reference.into(),
+            value: value.into(),
+            package: package.into(),
+            ..ComponentRecord::placeholder()
+        }
+    }
+
+    #[test]
+    fn group_by_part() {
+        let mut sheet = ManualSheet::default();
+        for (reference, value) in [("R10", "10k"), ("C1", "100nF"), ("R2", "10k"), ("R1", "1k")] {
+            sheet.push(&component(reference, value, "0402"), "Skipped".into());
+        }
+        let groups: Vec<Vec<&str>> = sheet
+            .groups()
+            .iter()
+            .map(|group| group.iter().map(|rec| rec.reference.as_str()).collect())
+            .collect();
+        assert_eq!(groups, [vec!["C1"], vec!["R2", "R10"], vec!["R1"]]);
+    }
+
+    #[test]
+    fn natural_designator_order() {
+        let mut references = ["C10", "C2", "C1A", "C1", "IC3", "C"];
+        references.sort_by(|a, b| compare_references(a, b));
+        assert_eq!(references, ["C", "C1", "C1A", "C2", "C10", "IC3"]);
+    }
+
+    #[test]
+    fn escape_html() {
+        let dir = std::env::temp_dir().join(format!("yy1_{}_manual", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut sheet = ManualSheet::default();
+        sheet.push(
+            &component("J1", "<USB> & \"C\"", "USB-C"),
+            "Not <placed>".into(),
+        );
+        let csv_path = sheet.write(&dir.join("job.csv")).unwrap();
+        assert!(csv_path.ends_with("job_manual.csv"));
+
+        let html = std::fs::read_to_string(dir.join("job_manual.html")).unwrap();
+        assert!(html.contains("<h1>Manual assembly (1 components)</h1>"));
+        assert!(html.contains("&lt;USB&gt; &amp; &quot;C&quot; - USB-C &times; 1"));
+        assert!(html.contains("<td>Not &lt;placed&gt;</td>"));
+        assert!(!html.contains("<USB>"));
+    }
+}
@@ -0,0 +1,237 @@
+use super::input::*;
+use super::*;
+
+#[derive(Debug, serde::Deserialize)]
+struct MappingEntry {
+    #[serde(rename = "Key")]
+    key: String,
+
+    #[serde(rename = "Value")]
+    value: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ColumnMapping {
+    reference: Vec<String>,
+    value: Vec<String>,
+    package: Vec<String>,
+    position_x: Vec<String>,
+    position_y: Vec<String>,
+    rotation: Vec<String>,
+    side: Vec<String>,
+    delimiter: Option<u8>,
+    decimal_comma: bool,
+    units: Units,
+    clockwise: bool,
+}
+
+impl ColumnMapping {
+    fn new(units: Units) -> Self {
+        let aliases = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+        Self {
+            reference: aliases(&["Ref", "Designator"]),
+            value: aliases(&["Val", "Value", "Comment"]),
+            package: aliases(&["Package", "Footprint"]),
+            position_x: aliases(&["PosX", "Mid X", "X"]),
+            position_y: aliases(&["PosY", "Mid Y", "Y"]),
+            rotation: aliases(&["Rot", "Rotation"]),
+            side: aliases(&["Side", "Layer"]),
+            delimiter: None,
+            decimal_comma: false,
+            units,
+            clockwise: false,
+        }
+    }
+
+    pub fn load(path: &str, units: Units) -> io::Result<Self> {
+        Self::parse(&read_text(path)?, units).map_err(|err| in_file(path, err))
+    }
+
+    fn parse(text: &str, units: Units) -> io::Result<Self> {
+        let mut mapping = Self::new(units);
+        for (line, entry) in parse_csv::<MappingEntry>(text, "invalid-mapping")? {
+            let error = |message: String| -> io::Error {
+                Diagnostic::error("invalid-mapping", message)
+                    .line(line)
+                    .into()
+            };
+            let aliases = || {
+                entry
+                    .value
+                    .split('|')
+                    .map(|alias| alias.trim().into())
+                    .collect()
+            };
+            let invalid = || error(format!("Invalid {} mapping", entry.key));
+            match entry.key.as_str() {
+                "Reference" => mapping.reference = aliases(),
+                "Value" => mapping.value = aliases(),
+                "Package" => mapping.package = aliases(),
+                "PositionX" => mapping.position_x = aliases(),
+                "PositionY" => mapping.position_y = aliases(),
+                "Rotation" => mapping.rotation = aliases(),
+                "Side" => mapping.side = aliases(),
+                "Delimiter" => {
+                    mapping.delimiter = match entry.value.as_str() {
+                        "tab" | "\\t" => Some(b'\t'),
+                        "space" => Some(b' '),
+                        val if val.len() == 1 => Some(val.as_bytes()[0]),
+                        _ => return Err(invalid()),
+                    }
+                }
+                "DecimalComma" => {
+                    mapping.decimal_comma = entry.value.parse().map_err(|_| invalid())?
+                }
+                "Units" => mapping.units = entry.value.parse().map_err(|_| invalid())?,
+                "RotationDirection" => {
+                    mapping.clockwise = match entry.value.as_str() {
+                        "ccw" => false,
+                        "cw" => true,
+                        _ => return Err(invalid()),
+                    }
+                }
+                key => return Err(error(format!("Unknown mapping key: {key}"))),
+            }
+        }
+        Ok(mapping)
+    }
+
+    pub fn read(&self, text: &str) -> io::Result<Vec<KiCadRecord>> {
+        let lines: Vec<&str> = text.lines().collect();
+        let header_idx = lines
+            .iter()
+            .position(|line| {
+                let line = line.to_lowercase();
+                self.reference
+                    .iter()
+                    .any(|alias| line.contains(&alias.to_lowercase()))
+            })
+            .ok_or_else(|| invalid_input("Placement header not found"))?;
+        let delimiter = match self.delimiter {
+            Some(b' ') => None,
+            Some(delimiter) => Some(delimiter),
+            None => detect_delimiter(lines[header_idx]),
+        };
+        let rows = read_rows(&lines[header_idx..], delimiter)?;
+        let ((_, header), rows) = rows
+            .split_first()
+            .ok_or_else(|| invalid_input("Placement header not found"))?;
+        let header_line = header_idx + 1;
+
+        let column = |aliases: &[String]| {
+            aliases.iter().find_map(|alias| {
+                header
+                    .iter()
+                    .position(|name| name.eq_ignore_ascii_case(alias))
+            })
+        };
+        let required = |aliases: &[String], name: &str| {
+            column(aliases)
+                .ok_or_else(|| invalid_input_at(format!("Missing {name} column"), header_line))
+        };
+        let reference = required(&self.reference, "reference")?;
+        let position_x = required(&self.position_x, "X position")?;
+        let position_y = required(&self.position_y, "Y position")?;
+        let rotation = required(&self.rotation, "rotation")?;
+        let value = column(&self.value);
+        let package = column(&self.package);
+        let side = column(&self.side);
+
+        let mut records = vec![];
+        for (line, row) in rows {
+            let line_no = header_idx + line;
+            if row.iter().all(String::is_empty) {
+                continue;
+            }
+            let field = |idx: usize| {
+                row.get(idx)
+                    .ok_or_else(|| invalid_input_at("Missing field", line_no))
+            };
+            let optional = |idx: Option<usize>| {
+                idx.and_then(|idx| row.get(idx))
+                    .cloned()
+                    .unwrap_or_default()
+            };
+            let number = |idx: usize| {
+                let val = field(idx)?;
+                if self.decimal_comma {
+                    parse_length(&val.replace(',', "."), self.units, line_no)
+                } else {
+                    parse_length(val, self.units, line_no)
+                }
+            };
+            let side = match side {
+                Some(idx) => {
+                    let side_name = field(idx)?;
+                    parse_side(side_name).ok_or_else(|| {
+                        invalid_input_at(format!("Invalid side \"{side_name}\""), line_no)
+                    })?
+                }
+                None => Side::Top,
+            };
+            let rotation = if self.decimal_comma {
+                parse_number(&field(rotation)?.replace(',', "."), line_no)?
+            } else {
+                parse_number(field(rotation)?, line_no)?
+            };
+            records.push(KiCadRecord {
+                reference: field(reference)?.clone(),
+                value: optional(value),
+                package: optional(package),
+                position_x: number(position_x)?,
+                position_y: number(position_y)?,
+                rotation: if self.clockwise { -rotation } else { rotation },
+                side,
+                ..Default::default()
+            });
+        }
+
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapping(profile: &str) -> ColumnMapping {
+        ColumnMapping::parse(profile, Units::Millimeters).unwrap()
+    }
+
+    #[test]
+    fn detect_delimiter_without_profile_delimiter() {
+        let mapping =
+            mapping("Key,Value\nReference,RefDes\nValue,Part Value\nPositionX,X\nPositionY,Y\n");
+        let records = mapping
+            .read("RefDes,Part Value,X,Y,Rotation\nR1,10k,1.5,2,90\n")
+            .unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].reference, "R1");
+        assert_eq!(records[0].value, "10k");
+        assert_eq!(records[0].position_x, 1.5);
+        assert_eq!(records[0].rotation, 90.0);
+    }
+
+    #[test]
+    fn profile_delimiter_and_decimal_comma() {
+        let mapping =
+            mapping("Key,Value\nDelimiter,;\nDecimalComma,true\nRotationDirection,cw\nUnits,mil\n");
+        let records = mapping
+            .read("Designator;Comment;X;Y;Rot;Layer\nC1;100n;1000,0;500;90;Bottom\n")
+            .unwrap();
+        assert_eq!(records[0].position_x, 25.4);
+        assert_eq!(records[0].position_y, 12.7);
+        assert_eq!(records[0].rotation, -90.0);
+        assert_eq!(records[0].side, Side::Bottom);
+    }
+
+    #[test]
+    fn whitespace_delimiter() {
+        let mapping = mapping("Key,Value\nDelimiter,space\n");
+        let records = mapping
+            .read("Ref  Val  Package  PosX  PosY  Rot\nU1  \"NE555 DIP\"  DIP-8  1  2  0\n")
+            .unwrap();
+        assert_eq!(records[0].value, "NE555 DIP");
+        assert_eq!(records[0].package, "DIP-8");
+    }
+}
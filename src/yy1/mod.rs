@@ -1,11 +1,32 @@
 use converter::YY1Converter;
+pub use diagnostics::{Diagnostic, Diagnostics, OutputFormat};
+use std::collections::HashMap;
 use std::io;
 
+mod altium;
+mod bom;
 mod converter;
+mod diagnostics;
+mod eagle;
+mod easyeda;
+mod feeders;
+mod filter;
+mod input;
+mod job;
+mod kicad;
+mod library;
+mod machine;
+mod manual;
+mod mapping;
+mod overrides;
 mod package;
 mod planner;
+mod pockets;
+mod sexpr;
+mod values;
+mod xml;
 
-#[derive(Clone, Debug, serde::Serialize)]
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct ComponentRecord {
     #[serde(rename = "Designator")]
     reference: String,
@@ -51,9 +72,38 @@ pub struct ComponentRecord {
 
     #[serde(skip)]
     part: String,
+
+    #[serde(skip)]
+    fields: HashMap<String, String>,
+
+    #[serde(skip)]
+    side: Side,
+
+    #[serde(skip)]
+    dnp: bool,
+
+    #[serde(skip)]
+    forced_head: Option<Head>,
 }
 
+const MPN_FIELDS: [&str; 6] = [
+    "MPN",
+    "Manufacturer Part",
+    "Manufacturer Part Number",
+    "Manufacturer_Part_Number",
+    "Mfr. Part #",
+    "PartNumber",
+];
+
 impl ComponentRecord {
+    /// Manufacturer part number from the design fields.
+    pub fn mpn(&self) -> &str {
+        MPN_FIELDS
+            .iter()
+            .find_map(|name| self.fields.get(*name))
+            .map_or("", String::as_str)
+    }
+
     pub fn placeholder() -> Self {
         Self {
             reference: "NC".into(),
@@ -71,11 +121,15 @@ impl ComponentRecord {
             skip: 0,
             nozzle: None,
             part: String::default(),
+            fields: HashMap::new(),
+            side: Side::Top,
+            dnp: false,
+            forced_head: None,
         }
     }
 }
 
-#[derive(Debug, serde::Deserialize)]
+#[derive(Debug, Default, serde::Deserialize)]
 pub struct KiCadRecord {
     #[serde(rename = "Ref")]
     reference: String,
@@ -94,6 +148,35 @@ pub struct KiCadRecord {
 
     #[serde(rename = "Rot")]
     rotation: f32,
+
+    #[serde(rename = "Side", default)]
+    side: Side,
+
+    #[serde(skip)]
+    dnp: bool,
+
+    #[serde(skip)]
+    part: String,
+
+    #[serde(skip)]
+    fields: HashMap<String, String>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    #[default]
+    Top,
+    Bottom,
+}
+
+impl Side {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Side::Top => "top",
+            Side::Bottom => "bottom",
+        }
+    }
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -106,6 +189,9 @@ pub struct BOMRecord {
 
     #[serde(rename = "Feeder")]
     feeder: u8,
+
+    #[serde(rename = "MPN")]
+    mpn: String,
 }
 
 #[derive(
@@ -142,40 +228,87 @@ pub enum Nozzle {
     YX06,
 }
 
-#[derive(Debug, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Deserialize)]
 pub struct FeederConfig {
     #[serde(rename = "Feeder")]
     feeder: u8,
 
-    #[serde(rename = "Package")]
+    #[serde(rename = "Package", default)]
     package: String,
 
-    #[serde(rename = "Value")]
+    #[serde(rename = "Value", default)]
     value: String,
 
-    #[serde(rename = "Rotation")]
+    #[serde(rename = "Rotation", default)]
     rotation: f32,
 
-    #[serde(rename = "PickHeight")]
+    #[serde(rename = "PickHeight", default)]
     pick_height: f32,
 
-    #[serde(rename = "PlaceHeight")]
+    #[serde(rename = "PlaceHeight", default)]
     place_height: f32,
 
-    #[serde(rename = "MountSpeed")]
+    #[serde(rename = "MountSpeed", default = "default_mount_speed")]
     mount_speed: u8,
 
-    #[serde(rename = "Nozzle")]
-    nozzle: Nozzle,
+    #[serde(rename = "Nozzle", default)]
+    nozzle: Option<Nozzle>,
 
-    #[serde(rename = "Mode")]
+    #[serde(rename = "Mode", default)]
     mode: u8,
 
-    #[serde(rename = "Skip")]
+    #[serde(rename = "Skip", default)]
     skip: u8,
 
-    #[serde(rename = "Part")]
+    #[serde(rename = "Part", default)]
     part: String,
+
+    #[serde(rename = "PickX", default)]
+    pick_x: Option<f32>,
+
+    #[serde(rename = "PickY", default)]
+    pick_y: Option<f32>,
+
+    #[serde(rename = "PitchX", default)]
+    pitch_x: Option<f32>,
+
+    #[serde(rename = "PitchY", default)]
+    pitch_y: Option<f32>,
+
+    #[serde(rename = "Rows", default)]
+    rows: Option<u16>,
+
+    #[serde(rename = "Columns", default)]
+    columns: Option<u16>,
+
+    #[serde(rename = "Start", default)]
+    start: Option<u16>,
+
+    #[serde(rename = "Pockets", default)]
+    pocket_count: Option<u16>,
+
+    #[serde(skip)]
+    line: usize,
+}
+
+fn default_mount_speed() -> u8 {
+    100
+}
+
+impl FeederConfig {
+    pub fn pockets(&self) -> Option<pockets::PocketGrid> {
+        Some(pockets::PocketGrid::new(
+            Position::new(self.pick_x?, self.pick_y?),
+            Size::new(
+                self.pitch_x.unwrap_or_default(),
+                self.pitch_y.unwrap_or_default(),
+            ),
+            self.rows.unwrap_or(1),
+            self.columns.or(self.pocket_count).unwrap_or(1),
+            self.start.unwrap_or(1),
+            self.pocket_count,
+        ))
+    }
 }
 
 impl From<KiCadRecord> for ComponentRecord {
@@ -195,12 +328,25 @@ impl From<KiCadRecord> for ComponentRecord {
             mode: 0,
             skip: 0,
             nozzle: None,
-            part: String::default(),
+            part: value.part,
+            fields: value.fields,
+            side: value.side,
+            dnp: value.dnp,
+            forced_head: None,
         }
     }
 }
 
-#[derive(Clone, Debug)]
+pub fn normalize_rotation(angle: f32) -> f32 {
+    match angle % 360.0 {
+        -0.0 => 0.0,
+        angle if angle <= -180.0 => angle + 360.0,
+        angle if angle > 180.0 => angle - 360.0,
+        angle => angle,
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct PanelConfig {
     rows: u8,
     columns: u8,
@@ -208,7 +354,7 @@ pub struct PanelConfig {
     explode: bool,
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct Size {
     width: f32,
     height: f32,
@@ -227,7 +373,7 @@ impl Size {
     }
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct Position {
     x: f32,
     y: f32,
@@ -247,7 +393,57 @@ impl Position {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Units {
+    #[default]
+    Millimeters,
+    Inches,
+    Mils,
+}
+
+impl Units {
+    pub fn scale(&self) -> f32 {
+        match self {
+            Units::Millimeters => 1.0,
+            Units::Inches => 25.4,
+            Units::Mils => 0.0254,
+        }
+    }
+
+    /// Parses a length in millimetres, honoring `mm`, `in` and `mil` suffixes.
+    /// Bare numbers are interpreted in these units.
+    pub fn parse_length(&self, val: &str) -> Option<f32> {
+        let val = val.trim();
+        let (val, units) = if let Some(val) = val.strip_suffix("mil") {
+            (val, Units::Mils)
+        } else if let Some(val) = val.strip_suffix("mm") {
+            (val, Units::Millimeters)
+        } else if let Some(val) = val.strip_suffix("in") {
+            (val, Units::Inches)
+        } else {
+            (val, *self)
+        };
+        val.trim()
+            .parse::<f32>()
+            .ok()
+            .map(|val| val * units.scale())
+    }
+}
+
+impl std::str::FromStr for Units {
+    type Err = String;
+
+    fn from_str(val: &str) -> Result<Self, Self::Err> {
+        match val {
+            "mm" => Ok(Units::Millimeters),
+            "in" | "inch" | "inches" => Ok(Units::Inches),
+            "mil" | "mils" => Ok(Units::Mils),
+            _ => Err(format!("Unsupported units: {val}")),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum Fiducial {
     Reference(String),
     Position(Position),
@@ -277,6 +473,20 @@ impl PanelConfig {
             )
         }
     }
+
+    pub fn from_fields(fields: &[&str]) -> Option<Self> {
+        match fields {
+            ["PanelizedPCB", "UnitLength", height, "UnitWidth", width, "Rows", rows, "Columns", columns, ..] => {
+                Some(Self {
+                    explode: false,
+                    rows: rows.parse().ok()?,
+                    columns: columns.parse().ok()?,
+                    size: Size::new(width.parse().ok()?, height.parse().ok()?),
+                })
+            }
+            _ => None,
+        }
+    }
 }
 
 impl Default for PanelConfig {
@@ -290,14 +500,14 @@ impl Default for PanelConfig {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum NozzleStation {
     Station1,
     Station2,
     Station3,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum Head {
     Head1 = 1,
     Head2 = 2,
@@ -314,7 +524,7 @@ impl std::ops::Not for Head {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct NozzleChange {
     enabled: bool,
     before_component: usize,
@@ -331,6 +541,35 @@ impl NozzleChange {
             state, self.before_component, self.head, self.drop_station, self.pickup_station
         )
     }
+
+    pub fn from_fields(fields: &[&str]) -> Option<Self> {
+        let station = |name: &str| match name {
+            "Station1" => Some(NozzleStation::Station1),
+            "Station2" => Some(NozzleStation::Station2),
+            "Station3" => Some(NozzleStation::Station3),
+            _ => None,
+        };
+        match fields {
+            ["NozzleChange", state, "BeforeComponent", before_component, head, "Drop", drop_station, "PickUp", pickup_station, ..] => {
+                Some(Self {
+                    enabled: match *state {
+                        "ON" => true,
+                        "OFF" => false,
+                        _ => return None,
+                    },
+                    before_component: before_component.parse().ok()?,
+                    head: match *head {
+                        "Head1" => Head::Head1,
+                        "Head2" => Head::Head2,
+                        _ => return None,
+                    },
+                    drop_station: station(drop_station)?,
+                    pickup_station: station(pickup_station)?,
+                })
+            }
+            _ => None,
+        }
+    }
 }
 
 impl Default for NozzleChange {
@@ -363,6 +602,69 @@ impl PackageMap {
     }
 }
 
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct ComponentOverride {
+    #[serde(rename = "Designator")]
+    reference: String,
+
+    #[serde(rename = "OffsetX", default)]
+    offset_x: Option<f32>,
+
+    #[serde(rename = "OffsetY", default)]
+    offset_y: Option<f32>,
+
+    #[serde(rename = "Rotation", default)]
+    rotation: Option<f32>,
+
+    #[serde(rename = "PickHeight", default)]
+    pick_height: Option<f32>,
+
+    #[serde(rename = "PlaceHeight", default)]
+    place_height: Option<f32>,
+
+    #[serde(rename = "MountSpeed", default)]
+    mount_speed: Option<u8>,
+
+    #[serde(rename = "Mode", default)]
+    mode: Option<u8>,
+
+    #[serde(rename = "Skip", default)]
+    skip: Option<u8>,
+
+    #[serde(rename = "Head", default)]
+    head: Option<Head>,
+
+    #[serde(rename = "Nozzle", default)]
+    nozzle: Option<Nozzle>,
+}
+
+impl ComponentOverride {
+    fn apply(&self, comp: &mut ComponentRecord) {
+        comp.position_x += self.offset_x.unwrap_or_default();
+        comp.position_y += self.offset_y.unwrap_or_default();
+        comp.rotation = normalize_rotation(comp.rotation + self.rotation.unwrap_or_default());
+        if let Some(pick_height) = self.pick_height {
+            comp.pick_height = pick_height;
+        }
+        if let Some(place_height) = self.place_height {
+            comp.place_height = place_height;
+        }
+        if let Some(mount_speed) = self.mount_speed {
+            comp.mount_speed = mount_speed;
+        }
+        if let Some(mode) = self.mode {
+            comp.mode = mode;
+        }
+        if let Some(skip) = self.skip {
+            comp.skip = skip;
+        }
+        if let Some(head) = self.head {
+            comp.head = head as _;
+            comp.forced_head = Some(head);
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Config {
     input_path: String,
@@ -371,10 +673,23 @@ pub struct Config {
     panel: PanelConfig,
     skip_until: Option<usize>,
     offset: Vec<Position>,
+    overall_offset: bool,
+    board_width: Option<f32>,
+    input_units: Units,
     feeder_config_path: Option<String>,
+    value_aliases_path: Option<String>,
+    part_library_path: Option<String>,
     nozzle_config_path: Option<String>,
     package_map_path: Option<String>,
-    fiducial: Option<Fiducial>,
+    design_bom_path: Option<String>,
+    machine_profile_path: Option<String>,
+    overrides_path: Option<String>,
+    column_map_path: Option<String>,
+    fiducial: Vec<Fiducial>,
+    measured_fiducial: Vec<Position>,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    variant: Option<String>,
 }
 
 impl Config {
@@ -383,12 +698,25 @@ impl Config {
             input_path,
             output_path,
             feeder_config_path: None,
+            value_aliases_path: None,
+            part_library_path: None,
             nozzle_config_path: None,
             package_map_path: None,
+            design_bom_path: None,
+            machine_profile_path: None,
+            overrides_path: None,
+            column_map_path: None,
             skip_until: None,
             panel: PanelConfig::default(),
             offset: vec![Position::zero()],
-            fiducial: None,
+            overall_offset: false,
+            board_width: None,
+            input_units: Units::default(),
+            fiducial: vec![],
+            measured_fiducial: vec![],
+            include: vec![],
+            exclude: vec![],
+            variant: None,
             bom: false,
         }
     }
@@ -418,6 +746,27 @@ impl Config {
         }
     }
 
+    pub fn overall_offset(self, enable: bool) -> Self {
+        Self {
+            overall_offset: enable,
+            ..self
+        }
+    }
+
+    pub fn board_width(self, val: Option<f32>) -> Self {
+        Self {
+            board_width: val,
+            ..self
+        }
+    }
+
+    pub fn input_units(self, val: Units) -> Self {
+        Self {
+            input_units: val,
+            ..self
+        }
+    }
+
     pub fn feeder_config_path(self, val: Option<String>) -> Self {
         Self {
             feeder_config_path: val,
@@ -425,6 +774,20 @@ impl Config {
         }
     }
 
+    pub fn value_aliases_path(self, val: Option<String>) -> Self {
+        Self {
+            value_aliases_path: val,
+            ..self
+        }
+    }
+
+    pub fn part_library_path(self, val: Option<String>) -> Self {
+        Self {
+            part_library_path: val,
+            ..self
+        }
+    }
+
     pub fn nozzle_config_path(self, val: Option<String>) -> Self {
         Self {
             nozzle_config_path: val,
@@ -439,19 +802,127 @@ impl Config {
         }
     }
 
-    pub fn fiducial(self, val: Option<Fiducial>) -> Self {
+    pub fn design_bom_path(self, val: Option<String>) -> Self {
+        Self {
+            design_bom_path: val,
+            ..self
+        }
+    }
+
+    pub fn machine_profile_path(self, val: Option<String>) -> Self {
+        Self {
+            machine_profile_path: val,
+            ..self
+        }
+    }
+
+    pub fn overrides_path(self, val: Option<String>) -> Self {
+        Self {
+            overrides_path: val,
+            ..self
+        }
+    }
+
+    pub fn column_map_path(self, val: Option<String>) -> Self {
+        Self {
+            column_map_path: val,
+            ..self
+        }
+    }
+
+    pub fn fiducial(self, val: Vec<Fiducial>) -> Self {
         Self {
             fiducial: val,
             ..self
         }
     }
+
+    pub fn measured_fiducial(self, val: Vec<Position>) -> Self {
+        Self {
+            measured_fiducial: val,
+            ..self
+        }
+    }
+
+    pub fn include(self, val: Vec<String>) -> Self {
+        Self {
+            include: val,
+            ..self
+        }
+    }
+
+    pub fn exclude(self, val: Vec<String>) -> Self {
+        Self {
+            exclude: val,
+            ..self
+        }
+    }
+
+    pub fn variant(self, val: Option<String>) -> Self {
+        Self {
+            variant: val,
+            ..self
+        }
+    }
+}
+
+pub fn inspect(path: &str, diagnostics: &mut Diagnostics) -> io::Result<()> {
+    let job = job::YY1Job::read(path)?;
+    println!("{}", job.panel.as_string());
+    for (idx, fiducial) in job.fiducial.iter().enumerate() {
+        println!("Fiducial {}: {:.3}:{:.3}", idx + 1, fiducial.x, fiducial.y);
+    }
+    println!(
+        "Overall offset: {:.3}:{:.3}",
+        job.overall_offset.x, job.overall_offset.y
+    );
+    for nozzle_change in job.nozzle_change.iter().filter(|change| change.enabled) {
+        println!("{}", nozzle_change.as_string());
+    }
+    println!(
+        "Components: {} ({} skipped)",
+        job.components.len(),
+        job.components.iter().filter(|comp| comp.skip != 0).count()
+    );
+
+    for problem in job.validate() {
+        diagnostics.push(Diagnostic::error("invalid-job", problem).file(path));
+    }
+    Ok(())
+}
+
+pub fn learn(
+    edited_path: &str,
+    generated_path: &str,
+    output_path: &str,
+    overrides_path: Option<&str>,
+    diagnostics: &mut Diagnostics,
+) -> io::Result<()> {
+    let edited = job::YY1Job::read(edited_path)?;
+    let generated = job::YY1Job::read(generated_path)?;
+    let previous = match overrides_path {
+        Some(path) => overrides::read_overrides(path)?,
+        None => HashMap::new(),
+    };
+    let overrides = overrides::learn(&edited, &generated, previous, diagnostics);
+    diagnostics.check()?;
+
+    let mut writer = csv::Writer::from_path(output_path)?;
+    for component_override in overrides {
+        writer.serialize(component_override)?;
+    }
+    writer.flush()
 }
 
-pub fn convert(config: Config) -> io::Result<()> {
-    let mut converter = YY1Converter::try_new(config)?;
+pub fn convert(config: Config, diagnostics: &mut Diagnostics) -> io::Result<()> {
+    let mut converter = YY1Converter::try_new(config, diagnostics)?;
     converter.panelize();
     converter.apply_offset();
-    converter.assign_nozzles();
+    converter.apply_correction(diagnostics)?;
+    converter.assign_nozzles(diagnostics);
     converter.apply_skip();
-    converter.write_files()
+    converter.assign_pockets();
+    converter.validate(diagnostics);
+    diagnostics.check()?;
+    converter.write_files(diagnostics)
 }
@@ -0,0 +1,229 @@
+use super::job::YY1Job;
+use super::*;
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+
+static PANEL_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(.+)_[0-9]+_[0-9]+$").unwrap());
+static OFFSET_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(.+)-[0-9]+$").unwrap());
+
+pub fn read_overrides(path: &str) -> io::Result<HashMap<String, ComponentOverride>> {
+    let mut reader = csv::Reader::from_path(path)?;
+    reader
+        .deserialize()
+        .map(|record| {
+            let record: ComponentOverride = record?;
+            Ok((record.reference.clone(), record))
+        })
+        .collect()
+}
+
+/// Panel (`_col_row`) and offset (`-idx`) suffixes the converter added to a generated job.
+///
+/// The converter adds them to every designator, so a suffix is only stripped when all
+/// designators of the job have it. Native designators like `J1-2` are kept otherwise.
+struct Suffixes {
+    panel: bool,
+    offset: bool,
+}
+
+impl Suffixes {
+    fn detect(references: &[&str]) -> Self {
+        let offset = !references.is_empty()
+            && references
+                .iter()
+                .all(|reference| OFFSET_RE.is_match(reference));
+        let suffixes = Self {
+            panel: false,
+            offset,
+        };
+        let panel = !references.is_empty()
+            && references
+                .iter()
+                .all(|reference| PANEL_RE.is_match(&suffixes.base_reference(reference)));
+        Self { panel, ..suffixes }
+    }
+
+    fn base_reference(&self, reference: &str) -> String {
+        let mut reference = reference.to_string();
+        for (enabled, re) in [(self.offset, &OFFSET_RE), (self.panel, &PANEL_RE)] {
+            if enabled {
+                reference = re.replace(&reference, "${1}").into();
+            }
+        }
+        reference
+    }
+}
+
+fn round(val: f32) -> f32 {
+    (val * 1000.0).round() / 1000.0
+}
+
+pub fn learn(
+    edited: &YY1Job,
+    generated: &YY1Job,
+    mut overrides: HashMap<String, ComponentOverride>,
+    diagnostics: &mut Diagnostics,
+) -> Vec<ComponentOverride> {
+    let generated: HashMap<&str, &ComponentRecord> = generated
+        .components
+        .iter()
+        .map(|comp| (comp.reference.as_str(), comp))
+        .collect();
+    let placeholder = ComponentRecord::placeholder();
+    let references: Vec<&str> = generated
+        .keys()
+        .copied()
+        .filter(|reference| *reference != placeholder.reference)
+        .collect();
+    let suffixes = Suffixes::detect(&references);
+
+    let mut edits: BTreeMap<String, Vec<ComponentOverride>> = BTreeMap::new();
+    for comp in edited
+        .components
+        .iter()
+        .filter(|comp| comp.reference != placeholder.reference)
+    {
+        let Some(origin) = generated.get(comp.reference.as_str()) else {
+            diagnostics.push(
+                Diagnostic::warning(
+                    "component-not-generated",
+                    format!(
+                        "Component {} not found in generated job. Skipping...",
+                        comp.reference
+                    ),
+                )
+                .designator(&comp.reference),
+            );
+            continue;
+        };
+        let delta = |edited: f32, origin: f32| {
+            let delta = round(edited - origin);
+            (delta != 0.0).then_some(delta)
+        };
+        let changed = |edited: f32, origin: f32| (edited != origin).then_some(edited);
+        let reference = suffixes.base_reference(&comp.reference);
+        edits
+            .entry(reference.clone())
+            .or_default()
+            .push(ComponentOverride {
+                reference,
+                offset_x: delta(comp.position_x, origin.position_x),
+                offset_y: delta(comp.position_y, origin.position_y),
+                rotation: delta(normalize_rotation(comp.rotation - origin.rotation), 0.0),
+                pick_height: changed(comp.pick_height, origin.pick_height),
+                place_height: changed(comp.place_height, origin.place_height),
+                mount_speed: (comp.mount_speed != origin.mount_speed).then_some(comp.mount_speed),
+                mode: (comp.mode != origin.mode).then_some(comp.mode),
+                skip: (comp.skip != origin.skip).then_some(comp.skip),
+                ..Default::default()
+            });
+    }
+
+    for (reference, copies) in edits {
+        let first = &copies[0];
+        let consistent = copies.iter().all(|copy| {
+            copy.offset_x == first.offset_x
+                && copy.offset_y == first.offset_y
+                && copy.rotation == first.rotation
+                && copy.pick_height == first.pick_height
+                && copy.place_height == first.place_height
+                && copy.mount_speed == first.mount_speed
+                && copy.mode == first.mode
+                && copy.skip == first.skip
+        });
+        if !consistent {
+            diagnostics.push(
+                Diagnostic::warning(
+                    "inconsistent-edits",
+                    format!("Inconsistent edits for component {reference}. Using first copy..."),
+                )
+                .designator(&reference),
+            );
+        }
+
+        let is_edited = first.offset_x.is_some()
+            || first.offset_y.is_some()
+            || first.rotation.is_some()
+            || first.pick_height.is_some()
+            || first.place_height.is_some()
+            || first.mount_speed.is_some()
+            || first.mode.is_some()
+            || first.skip.is_some();
+        if !is_edited {
+            continue;
+        }
+
+        let entry = overrides
+            .entry(reference.clone())
+            .or_insert(ComponentOverride {
+                reference,
+                ..Default::default()
+            });
+        let accumulate = |prev: Option<f32>, delta: Option<f32>| match (prev, delta) {
+            (Some(prev), Some(delta)) => Some(round(prev + delta)),
+            (prev, delta) => prev.or(delta),
+        };
+        entry.offset_x = accumulate(entry.offset_x, first.offset_x);
+        entry.offset_y = accumulate(entry.offset_y, first.offset_y);
+        entry.rotation = accumulate(entry.rotation, first.rotation).map(normalize_rotation);
+        entry.pick_height = first.pick_height.or(entry.pick_height);
+        entry.place_height = first.place_height.or(entry.place_height);
+        entry.mount_speed = first.mount_speed.or(entry.mount_speed);
+        entry.mode = first.mode.or(entry.mode);
+        entry.skip = first.skip.or(entry.skip);
+    }
+
+    let mut overrides: Vec<ComponentOverride> = overrides.into_values().collect();
+    overrides.sort_by(|a, b| a.reference.cmp(&b.reference));
+    overrides
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base_references(references: &[&str]) -> Vec<String> {
+        let suffixes = Suffixes::detect(references);
+        references
+            .iter()
+            .map(|reference| suffixes.base_reference(reference))
+            .collect()
+    }
+
+    #[test]
+    fn strip_generated_suffixes() {
+        assert_eq!(base_references(&["R1_1_2-1", "J1-2_2_1-2"]), ["R1", "J1-2"]);
+        assert_eq!(base_references(&["R1_1_2", "C10_3_1"]), ["R1", "C10"]);
+        assert_eq!(base_references(&["R1-1", "J1-2-2"]), ["R1", "J1-2"]);
+    }
+
+    #[test]
+    fn keep_native_designators() {
+        assert_eq!(base_references(&["R1", "J1-2"]), ["R1", "J1-2"]);
+        assert_eq!(base_references(&["U1_A", "J1-2"]), ["U1_A", "J1-2"]);
+        assert_eq!(base_references(&["R1-1", "R1_1_1"]), ["R1-1", "R1_1_1"]);
+    }
+
+    #[test]
+    fn apply_override() {
+        let text = "Designator,OffsetX,Rotation,PlaceHeight,Mode,Skip,Head,Nozzle\n\
+                    U1,0.5,90,2.5,1,1,Head2,CN220\n";
+        let mut reader = csv::Reader::from_reader(text.as_bytes());
+        let component_override: ComponentOverride = reader.deserialize().next().unwrap().unwrap();
+        assert_eq!(component_override.nozzle, Some(Nozzle::CN220));
+
+        let mut comp = ComponentRecord {
+            position_x: 10.0,
+            rotation: 270.0,
+            ..ComponentRecord::placeholder()
+        };
+        component_override.apply(&mut comp);
+        assert_eq!(comp.position_x, 10.5);
+        assert_eq!(comp.rotation, 0.0);
+        assert_eq!(comp.pick_height, 15.0);
+        assert_eq!(comp.place_height, 2.5);
+        assert_eq!((comp.mode, comp.skip, comp.head), (1, 1, 2));
+        assert_eq!(comp.forced_head, Some(Head::Head2));
+    }
+}
@@ -18,9 +18,27 @@ pub struct NozzleConfig {
 
     #[serde(skip)]
     station3: Option<Nozzle>,
+
+    #[serde(skip)]
+    stations: u8,
 }
 
 impl NozzleConfig {
+    pub fn with_stations(self, stations: u8) -> io::Result<Self> {
+        if stations < 2 && self.station2.is_some() {
+            return Err(io::Error::other("Nozzle station 2 is not available"));
+        }
+        let occupied = [self.station1, self.station2]
+            .iter()
+            .take(stations as usize)
+            .filter(|nozzle| nozzle.is_some())
+            .count();
+        if occupied >= stations as usize {
+            return Err(io::Error::other("No free nozzle station in nozzle config"));
+        }
+        Ok(Self { stations, ..self })
+    }
+
     pub fn contains(&self, nozzle: Nozzle) -> bool {
         let nozzle = Some(nozzle);
         self.head1 == nozzle
@@ -68,10 +86,10 @@ impl NozzleConfig {
         if self.station1.is_none() {
             self.station1 = nozzle;
             NozzleStation::Station1
-        } else if self.station2.is_none() {
+        } else if self.station2.is_none() && self.stations >= 2 {
             self.station2 = nozzle;
             NozzleStation::Station2
-        } else if self.station3.is_none() {
+        } else if self.station3.is_none() && self.stations >= 3 {
             self.station3 = nozzle;
             NozzleStation::Station3
         } else {
@@ -157,24 +175,16 @@ impl Planner {
             .collect()
     }
 
-    pub fn plan(&mut self, nozzle: Nozzle) -> PlannerAction {
-        let active_nozzle = self.config.get_head_nozzle(self.head);
-        let nozzle_expired = active_nozzle.is_none()
-            || self
-                .nozzle_spans
-                .get(&active_nozzle.unwrap())
-                .map(|ttl| *ttl < self.component_index)
-                .unwrap_or(true);
-
-        if nozzle_expired && self.config.find_nozzle_station(nozzle).is_some() {
-            let nozzle_change = self
-                .config
-                .pickup_nozzle(self.head, nozzle, self.component_index);
-            self.nozzle_history.push(nozzle_change);
-            return PlannerAction::NozzleChange(nozzle_change);
+    pub fn plan(&mut self, nozzle: Nozzle, forced_head: Option<Head>) -> PlannerAction {
+        // A forced head is only used when it can take the nozzle, otherwise plan as usual
+        if let Some(head) = forced_head.filter(|head| self.can_take(*head, nozzle)) {
+            self.head = head;
+        }
+        if self.nozzle_expired(self.head) && self.config.find_nozzle_station(nozzle).is_some() {
+            return self.change_nozzle(self.head, nozzle);
         }
 
-        if active_nozzle == Some(nozzle) {
+        if self.config.get_head_nozzle(self.head) == Some(nozzle) {
             let action = PlannerAction::Head(self.head);
             self.component_index += 1;
             self.head = !self.head;
@@ -182,8 +192,134 @@ impl Planner {
         } else if self.config.get_head_nozzle(!self.head) == Some(nozzle) {
             self.component_index += 1;
             PlannerAction::Head(!self.head)
+        } else if self.nozzle_expired(!self.head) {
+            self.change_nozzle(!self.head, nozzle)
         } else {
-            unreachable!()
+            self.change_nozzle(self.head, nozzle)
+        }
+    }
+
+    fn change_nozzle(&mut self, head: Head, nozzle: Nozzle) -> PlannerAction {
+        let nozzle_change = self
+            .config
+            .pickup_nozzle(head, nozzle, self.component_index);
+        self.nozzle_history.push(nozzle_change);
+        PlannerAction::NozzleChange(nozzle_change)
+    }
+
+    fn nozzle_expired(&self, head: Head) -> bool {
+        self.config
+            .get_head_nozzle(head)
+            .and_then(|nozzle| self.nozzle_spans.get(&nozzle))
+            .map_or(true, |ttl| *ttl < self.component_index)
+    }
+
+    fn can_take(&self, head: Head, nozzle: Nozzle) -> bool {
+        self.config.get_head_nozzle(head) == Some(nozzle)
+            || (self.nozzle_expired(head) && self.config.find_nozzle_station(nozzle).is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nozzle_config(text: &str) -> NozzleConfig {
+        let mut reader = csv::Reader::from_reader(text.as_bytes());
+        let config: NozzleConfig = reader.deserialize().next().unwrap().unwrap();
+        config.with_stations(3).unwrap()
+    }
+
+    /// Plans the components and returns the heads used and the number of nozzle changes.
+    fn plan(config: NozzleConfig, components: &[ComponentRecord]) -> (Vec<Head>, usize) {
+        let mut planner = Planner::new(config, components);
+        let mut heads = vec![];
+        let mut changes = 0;
+        for comp in components {
+            loop {
+                match planner.plan(comp.nozzle.unwrap(), comp.forced_head) {
+                    PlannerAction::Head(head) => {
+                        heads.push(head);
+                        break;
+                    }
+                    PlannerAction::NozzleChange(_) => changes += 1,
+                }
+            }
         }
+        (heads, changes)
+    }
+
+    fn component(nozzle: Nozzle, forced_head: Option<Head>) -> ComponentRecord {
+        ComponentRecord {
+            nozzle: Some(nozzle),
+            forced_head,
+            ..ComponentRecord::placeholder()
+        }
+    }
+
+    #[test]
+    fn station_limits() {
+        let config = |text: &str| -> NozzleConfig {
+            let mut reader = csv::Reader::from_reader(text.as_bytes());
+            reader.deserialize().next().unwrap().unwrap()
+        };
+        let one_free = config("Head1,Head2,Station1,Station2\nCN040,CN065,CN140,\n");
+        let both_used = config("Head1,Head2,Station1,Station2\nCN040,CN065,CN140,CN220\n");
+
+        assert!(one_free.with_stations(2).is_ok());
+        assert!(one_free.with_stations(1).is_err());
+        assert!(both_used.with_stations(3).is_ok());
+        assert!(both_used.with_stations(2).is_err());
+        assert!(both_used.with_stations(1).is_err());
+        let empty = config("Head1,Head2,Station1,Station2\nCN040,CN065,,\n");
+        assert!(empty.with_stations(1).is_ok());
+
+        // The nozzle dropped by the first change goes to the free third station
+        let mut config = both_used.with_stations(3).unwrap();
+        let change = config.pickup_nozzle(Head::Head1, Nozzle::CN140, 1);
+        assert_eq!(change.drop_station, NozzleStation::Station3);
+        assert_eq!(change.pickup_station, NozzleStation::Station1);
+        assert_eq!(
+            config.find_nozzle_station(Nozzle::CN040),
+            Some(NozzleStation::Station3)
+        );
+    }
+
+    #[test]
+    fn alternate_heads() {
+        let config = nozzle_config("Head1,Head2,Station1,Station2\nCN040,CN065,CN140,\n");
+        let components = [
+            component(Nozzle::CN040, None),
+            component(Nozzle::CN065, None),
+            component(Nozzle::CN140, None),
+        ];
+        let (heads, changes) = plan(config, &components);
+        assert_eq!(heads, [Head::Head1, Head::Head2, Head::Head1]);
+        assert_eq!(changes, 1);
+    }
+
+    #[test]
+    fn forced_head() {
+        let config = nozzle_config("Head1,Head2,Station1,Station2\nCN040,CN065,CN140,\n");
+        let components = [
+            component(Nozzle::CN040, Some(Head::Head2)),
+            component(Nozzle::CN140, Some(Head::Head2)),
+        ];
+        let (heads, changes) = plan(config, &components);
+        assert_eq!(heads, [Head::Head1, Head::Head2]);
+        assert_eq!(changes, 1);
+    }
+
+    #[test]
+    fn ignore_forced_head_in_use() {
+        let config = nozzle_config("Head1,Head2,Station1,Station2\nCN040,CN065,CN140,CN220\n");
+        let components = [
+            component(Nozzle::CN140, None),
+            component(Nozzle::CN220, Some(Head::Head1)),
+            component(Nozzle::CN140, None),
+        ];
+        let (heads, changes) = plan(config, &components);
+        assert_eq!(heads, [Head::Head1, Head::Head2, Head::Head1]);
+        assert_eq!(changes, 2);
     }
 }
@@ -0,0 +1,251 @@
+use super::*;
+
+/// Grid of component pockets (IC tray or cut-tape strip) picked row by row, starting at `start`.
+#[derive(Clone, Debug)]
+pub struct PocketGrid {
+    origin: Position,
+    pitch: Size,
+    rows: u16,
+    columns: u16,
+    start: u16,
+    count: Option<u16>,
+}
+
+impl PocketGrid {
+    pub fn new(
+        origin: Position,
+        pitch: Size,
+        rows: u16,
+        columns: u16,
+        start: u16,
+        count: Option<u16>,
+    ) -> Self {
+        Self {
+            origin,
+            pitch,
+            rows,
+            columns,
+            start,
+            count,
+        }
+    }
+
+    pub fn capacity(&self) -> u16 {
+        let capacity = self.rows.saturating_mul(self.columns);
+        self.count.map_or(capacity, |count| count.min(capacity))
+    }
+
+    /// Returns the position of the 1-based pocket, or `None` if the grid has no such pocket.
+    pub fn position(&self, pocket: u16) -> Option<Position> {
+        if pocket == 0 || pocket > self.capacity() {
+            return None;
+        }
+        let idx = pocket - 1;
+        let row = idx / self.columns;
+        let column = idx % self.columns;
+        Some(Position::new(
+            self.origin.x + column as f32 * self.pitch.width,
+            self.origin.y + row as f32 * self.pitch.height,
+        ))
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PickRecord {
+    #[serde(rename = "Designator")]
+    reference: String,
+
+    #[serde(rename = "FeederNo")]
+    feeder: u8,
+
+    #[serde(rename = "Pocket")]
+    pocket: u16,
+
+    #[serde(rename = "Pick X(mm)")]
+    position_x: f32,
+
+    #[serde(rename = "Pick Y(mm)")]
+    position_y: f32,
+}
+
+/// Assigns successive pockets to components picked from pocket feeders.
+///
+/// This is a report only: a YY1 job has no per-component pick position, so the machine
+/// still picks from the position taught for the feeder. A feeder used for more than one
+/// placement in a job is reported, as the machine would pick each of them from the same pocket.
+pub struct PocketTracker {
+    grids: HashMap<u8, PocketGrid>,
+    next: HashMap<u8, u16>,
+    missing: HashMap<u8, Vec<String>>,
+    picks: Vec<PickRecord>,
+    job_picks: HashMap<u8, usize>,
+    repeated: Vec<(u8, String)>,
+}
+
+impl PocketTracker {
+    pub fn new(grids: HashMap<u8, PocketGrid>) -> Self {
+        Self {
+            grids,
+            next: HashMap::new(),
+            missing: HashMap::new(),
+            picks: vec![],
+            job_picks: HashMap::new(),
+            repeated: vec![],
+        }
+    }
+
+    /// Starts counting the placements of the next job file.
+    pub fn next_job(&mut self) {
+        self.job_picks.clear();
+    }
+
+    pub fn pick(&mut self, comp: &ComponentRecord) {
+        let Some(grid) = self.grids.get(&comp.feeder) else {
+            return;
+        };
+        let job_picks = self.job_picks.entry(comp.feeder).or_default();
+        *job_picks += 1;
+        if *job_picks == 2 {
+            self.repeated.push((comp.feeder, comp.reference.clone()));
+        }
+        let pocket = self.next.entry(comp.feeder).or_insert(grid.start);
+        match grid.position(*pocket) {
+            Some(position) => {
+                self.picks.push(PickRecord {
+                    reference: comp.reference.clone(),
+                    feeder: comp.feeder,
+                    pocket: *pocket,
+                    position_x: position.x,
+                    position_y: position.y,
+                });
+                *pocket = pocket.saturating_add(1);
+            }
+            None => self
+                .missing
+                .entry(comp.feeder)
+                .or_default()
+                .push(comp.reference.clone()),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.next.is_empty()
+    }
+
+    pub fn picks(&self) -> &[PickRecord] {
+        &self.picks
+    }
+
+    pub fn report(&self, diagnostics: &mut Diagnostics) {
+        let mut feeders: Vec<&u8> = self.next.keys().collect();
+        feeders.sort();
+        for feeder in feeders {
+            let grid = &self.grids[feeder];
+            let next = self.next[feeder];
+            let diagnostic = match self.missing.get(feeder) {
+                Some(missing) => Diagnostic::warning(
+                    "feeder-refill",
+                    format!(
+                        "Feeder #{feeder} runs out of parts, {} more needed. Refill before {}.",
+                        missing.len(),
+                        missing[0]
+                    ),
+                )
+                .designator(&missing[0]),
+                None if next > grid.capacity() => Diagnostic::info(
+                    "feeder-pockets",
+                    format!("Feeder #{feeder} is empty after this job."),
+                ),
+                None => Diagnostic::info(
+                    "feeder-pockets",
+                    format!("Feeder #{feeder} continues at pocket {next}."),
+                ),
+            };
+            diagnostics.push(diagnostic);
+        }
+        for (feeder, reference) in &self.repeated {
+            diagnostics.push(
+                Diagnostic::warning(
+                    "feeder-pocket-reuse",
+                    format!(
+                        "Feeder #{feeder} serves more than one placement in this job, but the \
+                         machine picks them all from the taught pocket. Reteach it before \
+                         {reference}."
+                    ),
+                )
+                .designator(reference),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(rows: u16, columns: u16, start: u16, count: Option<u16>) -> PocketGrid {
+        PocketGrid::new(
+            Position::new(100.0, 50.0),
+            Size::new(12.0, 10.0),
+            rows,
+            columns,
+            start,
+            count,
+        )
+    }
+
+    #[test]
+    fn pockets_are_picked_row_by_row() {
+        let tray = grid(2, 3, 1, None);
+        assert_eq!(tray.capacity(), 6);
+        let position = tray.position(5).unwrap();
+        assert_eq!((position.x, position.y), (112.0, 60.0));
+        assert!(tray.position(0).is_none());
+        assert!(tray.position(7).is_none());
+        assert_eq!(grid(1, 10, 1, Some(4)).capacity(), 4);
+    }
+
+    #[test]
+    fn large_trays_do_not_overflow() {
+        assert_eq!(grid(1000, 1000, 1, None).capacity(), u16::MAX);
+        assert!(grid(0, 0, 1, None).position(1).is_none());
+    }
+
+    #[test]
+    fn tracker_continues_at_start_pocket() {
+        let mut tracker = PocketTracker::new(HashMap::from([(70, grid(1, 3, 2, None))]));
+        for reference in ["U1", "U2", "U3"] {
+            tracker.pick(&ComponentRecord {
+                reference: reference.into(),
+                feeder: 70,
+                ..ComponentRecord::placeholder()
+            });
+        }
+        let pockets: Vec<u16> = tracker.picks().iter().map(|pick| pick.pocket).collect();
+        assert_eq!(pockets, [2, 3]);
+        assert_eq!(tracker.missing[&70], ["U3"]);
+    }
+
+    #[test]
+    fn report_pocket_reuse_per_job() {
+        let mut tracker = PocketTracker::new(HashMap::from([(70, grid(2, 5, 1, None))]));
+        let pick = |tracker: &mut PocketTracker, reference: &str| {
+            tracker.pick(&ComponentRecord {
+                reference: reference.into(),
+                feeder: 70,
+                ..ComponentRecord::placeholder()
+            })
+        };
+        pick(&mut tracker, "U1");
+        tracker.next_job();
+        pick(&mut tracker, "U2");
+        assert!(tracker.repeated.is_empty());
+        pick(&mut tracker, "U3");
+        pick(&mut tracker, "U4");
+        assert_eq!(tracker.repeated, [(70, "U3".to_string())]);
+
+        let mut diagnostics = Diagnostics::new(OutputFormat::Json, true);
+        tracker.report(&mut diagnostics);
+        assert!(diagnostics.has_errors());
+    }
+}
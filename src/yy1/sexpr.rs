@@ -0,0 +1,141 @@
+use super::input::invalid_input_at;
+use std::io;
+
+#[derive(Debug, Clone)]
+pub enum Sexpr {
+    Atom(String),
+    List(Vec<Sexpr>),
+}
+
+impl Sexpr {
+    pub fn parse(text: &str) -> io::Result<Sexpr> {
+        let mut stack: Vec<Vec<Sexpr>> = vec![vec![]];
+        let mut chars = text.chars().peekable();
+        let mut line = 1;
+        while let Some(ch) = chars.next() {
+            match ch {
+                '(' => stack.push(vec![]),
+                ')' => {
+                    let list = stack
+                        .pop()
+                        .filter(|_| !stack.is_empty())
+                        .ok_or_else(|| invalid_input_at("Unbalanced parentheses", line))?;
+                    stack.last_mut().unwrap().push(Sexpr::List(list));
+                }
+                '"' => {
+                    let start = line;
+                    let mut atom = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('"') => break,
+                            Some('\\') => match chars.next() {
+                                Some('n') => atom.push('\n'),
+                                Some(ch) => atom.push(ch),
+                                None => return Err(invalid_input_at("Unterminated string", start)),
+                            },
+                            Some(ch) => {
+                                if ch == '\n' {
+                                    line += 1;
+                                }
+                                atom.push(ch)
+                            }
+                            None => return Err(invalid_input_at("Unterminated string", start)),
+                        }
+                    }
+                    stack.last_mut().unwrap().push(Sexpr::Atom(atom));
+                }
+                '\n' => line += 1,
+                ch if ch.is_whitespace() => {}
+                ch => {
+                    let mut atom = String::from(ch);
+                    while let Some(&ch) = chars.peek() {
+                        if ch.is_whitespace() || ch == '(' || ch == ')' {
+                            break;
+                        }
+                        atom.push(ch);
+                        chars.next();
+                    }
+                    stack.last_mut().unwrap().push(Sexpr::Atom(atom));
+                }
+            }
+        }
+
+        match stack.pop() {
+            Some(mut root) if stack.is_empty() && root.len() == 1 => Ok(root.remove(0)),
+            _ => Err(invalid_input_at("Unbalanced parentheses", line)),
+        }
+    }
+
+    pub fn atom(&self) -> Option<&str> {
+        match self {
+            Sexpr::Atom(atom) => Some(atom),
+            Sexpr::List(_) => None,
+        }
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Sexpr::List(items) => items.first().and_then(Sexpr::atom),
+            Sexpr::Atom(_) => None,
+        }
+    }
+
+    pub fn args(&self) -> impl Iterator<Item = &str> {
+        let items = match self {
+            Sexpr::List(items) => items.get(1..).unwrap_or_default(),
+            Sexpr::Atom(_) => &[],
+        };
+        items.iter().filter_map(Sexpr::atom)
+    }
+
+    pub fn arg(&self, idx: usize) -> Option<&str> {
+        self.args().nth(idx)
+    }
+
+    pub fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Sexpr> {
+        let items = match self {
+            Sexpr::List(items) => &items[..],
+            Sexpr::Atom(_) => &[],
+        };
+        items.iter().filter(move |item| item.name() == Some(name))
+    }
+
+    pub fn child(&self, name: &str) -> Option<&Sexpr> {
+        match self {
+            Sexpr::List(items) => items.iter().find(|item| item.name() == Some(name)),
+            Sexpr::Atom(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_nested_lists() {
+        let expr = Sexpr::parse(
+            r#"(footprint "Lib:R 0603" (layer F.Cu) (property "Value" "say \"hi\"") (at 1 -2.5))"#,
+        )
+        .unwrap();
+        assert_eq!(expr.name(), Some("footprint"));
+        assert_eq!(expr.arg(0), Some("Lib:R 0603"));
+        assert_eq!(
+            expr.child("layer").and_then(|layer| layer.arg(0)),
+            Some("F.Cu")
+        );
+        let property = expr.children("property").next().unwrap();
+        assert_eq!(property.arg(1), Some("say \"hi\""));
+        let at: Vec<&str> = expr.child("at").unwrap().args().collect();
+        assert_eq!(at, ["1", "-2.5"]);
+        assert!(expr.child("missing").is_none());
+    }
+
+    #[test]
+    fn reject_unbalanced() {
+        assert!(Sexpr::parse("(a (b)").is_err());
+        assert!(Sexpr::parse("(a))").is_err());
+        assert!(Sexpr::parse("(a \"b)").is_err());
+        assert!(Sexpr::parse("(a) (b)").is_err());
+    }
+}
@@ -0,0 +1,174 @@
+use super::input::read_csv;
+use super::*;
+use regex::Regex;
+use std::sync::LazyLock;
+
+static VALUE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?P<int>[0-9]+)(?:[.,](?P<frac>[0-9]+))?\s*(?:(?P<prefix>[pnuµμmkKMGR])(?P<rkm>[0-9]+)?)?\s*(?P<unit>F|H|Ω|[Oo]hms?)?(?P<rest>[\s/_,].*)?$",
+    )
+    .unwrap()
+});
+
+/// Tolerance or voltage rating after the value, e.g. `10%` or `16V`.
+static SUFFIX_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?:±?[0-9]+(?:[.,][0-9]+)?%|[0-9]+(?:[.,][0-9]+)?[kK]?V)$").unwrap()
+});
+
+#[derive(Debug, Clone, serde::Deserialize)]
+struct ValueAlias {
+    #[serde(rename = "Alias")]
+    alias: String,
+
+    #[serde(rename = "Value")]
+    value: String,
+}
+
+/// Value in base units with an optional unit symbol (`F`, `H` or `Ω`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct EngValue {
+    value: f64,
+    unit: Option<char>,
+}
+
+impl EngValue {
+    /// Parses values like `100n`, `0.1uF`, `4k7`, `4R7` or `100nF 16V 10%`.
+    fn parse(val: &str) -> Option<Self> {
+        let caps = VALUE_RE.captures(val.trim())?;
+        let rest = caps.name("rest").map_or("", |rest| rest.as_str());
+        if !rest
+            .split(|ch: char| ch.is_whitespace() || "/_,".contains(ch))
+            .filter(|token| !token.is_empty())
+            .all(|token| SUFFIX_RE.is_match(token))
+        {
+            return None;
+        }
+
+        let prefix = caps.name("prefix").map(|prefix| prefix.as_str());
+        let frac = match (caps.name("frac"), caps.name("rkm")) {
+            (Some(_), Some(_)) => return None,
+            (Some(frac), None) | (None, Some(frac)) => frac.as_str(),
+            (None, None) => "",
+        };
+        let mantissa: f64 = format!("{}.{frac}", &caps["int"]).parse().ok()?;
+        let multiplier = match prefix {
+            Some("p") => 1e-12,
+            Some("n") => 1e-9,
+            Some("u" | "µ" | "μ") => 1e-6,
+            Some("m") => 1e-3,
+            Some("k" | "K") => 1e3,
+            Some("M") => 1e6,
+            Some("G") => 1e9,
+            _ => 1.0,
+        };
+        let unit = match caps.name("unit").map(|unit| unit.as_str()) {
+            Some("F") => Some('F'),
+            Some("H") => Some('H'),
+            Some(_) => Some('Ω'),
+            None if prefix == Some("R") => Some('Ω'),
+            None => None,
+        };
+
+        Some(Self {
+            value: mantissa * multiplier,
+            unit,
+        })
+    }
+
+    /// Formats the value in engineering notation, e.g. `100n` for 0.1µ.
+    fn format(&self, with_unit: bool) -> String {
+        let prefixes = ["p", "n", "u", "m", "", "k", "M", "G"];
+        let mut idx = 4;
+        let mut mantissa = self.value;
+        if mantissa != 0.0 {
+            while mantissa.abs() < 1.0 - 1e-9 && idx > 0 {
+                mantissa *= 1e3;
+                idx -= 1;
+            }
+            while mantissa.abs() >= 1e3 - 1e-6 && idx < prefixes.len() - 1 {
+                mantissa /= 1e3;
+                idx += 1;
+            }
+        }
+        let mantissa = format!("{mantissa:.3}");
+        let mantissa = mantissa.trim_end_matches('0').trim_end_matches('.');
+        let unit = self.unit.filter(|_| with_unit).map(String::from);
+        format!("{mantissa}{}{}", prefixes[idx], unit.unwrap_or_default())
+    }
+}
+
+/// Normalizes component values so equivalent notations match the same feeder.
+#[derive(Debug, Clone, Default)]
+pub struct ValueNormalizer {
+    aliases: HashMap<String, String>,
+}
+
+impl ValueNormalizer {
+    pub fn load(path: &str) -> io::Result<Self> {
+        let mut normalizer = Self::default();
+        for (_, record) in read_csv::<ValueAlias>(path, "invalid-aliases")? {
+            let value = normalizer.canonical(&record.value, true);
+            normalizer
+                .aliases
+                .insert(normalizer.canonical(&record.alias, true), value);
+        }
+        Ok(normalizer)
+    }
+
+    fn canonical(&self, val: &str, with_unit: bool) -> String {
+        match EngValue::parse(val) {
+            Some(value) => value.format(with_unit),
+            None => val.trim().into(),
+        }
+    }
+
+    /// Returns the normalized value, resolving aliases.
+    pub fn normalize(&self, val: &str) -> String {
+        let canonical = self.canonical(val, true);
+        self.aliases.get(&canonical).cloned().unwrap_or(canonical)
+    }
+
+    /// Returns the normalized value without its unit symbol, e.g. `100n` for `100nF`.
+    pub fn normalize_unitless(&self, val: &str) -> String {
+        self.canonical(&self.normalize(val), false)
+    }
+
+    /// Checks if the normalized value has a unit symbol.
+    pub fn has_unit(&self, val: &str) -> bool {
+        EngValue::parse(&self.normalize(val)).is_some_and(|value| value.unit.is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_notations() {
+        let normalizer = ValueNormalizer::default();
+        assert_eq!(normalizer.normalize("100n"), "100n");
+        assert_eq!(normalizer.normalize("0.1uF"), "100nF");
+        assert_eq!(normalizer.normalize("100nF 16V 10%"), "100nF");
+        assert_eq!(normalizer.normalize("4k7"), "4.7k");
+        assert_eq!(normalizer.normalize("4,7k"), "4.7k");
+        assert_eq!(normalizer.normalize("4R7"), "4.7Ω");
+        assert_eq!(normalizer.normalize("10 kOhm"), "10kΩ");
+        assert_eq!(normalizer.normalize("1000pF"), "1nF");
+        assert_eq!(normalizer.normalize("BC847"), "BC847");
+    }
+
+    #[test]
+    fn unitless_and_unit() {
+        let normalizer = ValueNormalizer::default();
+        assert_eq!(normalizer.normalize_unitless("100nF"), "100n");
+        assert!(normalizer.has_unit("100nF"));
+        assert!(!normalizer.has_unit("100n"));
+        assert!(!normalizer.has_unit("BC847"));
+    }
+
+    #[test]
+    fn reject_mixed_fraction() {
+        assert_eq!(EngValue::parse("4.7k7"), None);
+        assert_eq!(EngValue::parse("100nF foo"), None);
+    }
+}
@@ -0,0 +1,219 @@
+use std::io;
+
+/// Minimal XML element tree, enough for KiCad netlist and BOM exports.
+#[derive(Debug, Clone, Default)]
+pub struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Element>,
+    text: String,
+}
+
+impl Element {
+    pub fn parse(text: &str) -> io::Result<Element> {
+        let mut stack: Vec<Element> = vec![Element::default()];
+        let mut rest = text;
+        while !rest.is_empty() {
+            let Some(start) = rest.find('<') else {
+                stack.last_mut().unwrap().text.push_str(&unescape(rest));
+                break;
+            };
+            stack
+                .last_mut()
+                .unwrap()
+                .text
+                .push_str(&unescape(&rest[..start]));
+            rest = &rest[start..];
+
+            if rest.starts_with("<!--") {
+                let end = rest
+                    .find("-->")
+                    .ok_or(io::Error::other("Unterminated XML comment"))?;
+                rest = &rest[end + 3..];
+                continue;
+            }
+            let end = find_tag_end(rest).ok_or(io::Error::other("Unterminated XML tag"))?;
+            let tag = &rest[1..end];
+            rest = &rest[end + 1..];
+            if tag.starts_with('?') || tag.starts_with('!') {
+                continue;
+            }
+
+            if let Some(name) = tag.strip_prefix('/') {
+                let element = stack
+                    .pop()
+                    .filter(|element| element.name == name.trim() && !stack.is_empty())
+                    .ok_or(io::Error::other(format!("Unexpected closing tag {name}")))?;
+                stack.last_mut().unwrap().children.push(element);
+                continue;
+            }
+
+            let (tag, closed) = match tag.strip_suffix('/') {
+                Some(tag) => (tag, true),
+                None => (tag, false),
+            };
+            let element = parse_tag(tag)?;
+            if closed {
+                stack.last_mut().unwrap().children.push(element);
+            } else {
+                stack.push(element);
+            }
+        }
+
+        match stack.pop() {
+            Some(mut root) if stack.is_empty() && root.children.len() == 1 => {
+                Ok(root.children.remove(0))
+            }
+            _ => Err(io::Error::other("Invalid XML document")),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, val)| val.as_str())
+    }
+
+    pub fn text(&self) -> &str {
+        self.text.trim()
+    }
+
+    pub fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+}
+
+/// Finds the `>` closing a tag, skipping quoted attribute values.
+fn find_tag_end(text: &str) -> Option<usize> {
+    let mut quote = None;
+    for (idx, ch) in text.char_indices() {
+        match (quote, ch) {
+            (None, '"' | '\'') => quote = Some(ch),
+            (None, '>') => return Some(idx),
+            (Some(open), ch) if ch == open => quote = None,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn parse_tag(tag: &str) -> io::Result<Element> {
+    let tag = tag.trim();
+    let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+    let mut element = Element {
+        name: tag[..name_end].into(),
+        ..Default::default()
+    };
+
+    let mut rest = tag[name_end..].trim_start();
+    while !rest.is_empty() {
+        let invalid = || io::Error::other(format!("Invalid XML attribute in <{}>", element.name));
+        let (key, val) = rest.split_once('=').ok_or_else(invalid)?;
+        let val = val.trim_start();
+        let quote = val.chars().next().filter(|ch| *ch == '"' || *ch == '\'');
+        let quote = quote.ok_or_else(invalid)?;
+        let end = val[1..].find(quote).ok_or_else(invalid)? + 1;
+        element
+            .attributes
+            .push((key.trim().into(), unescape(&val[1..end])));
+        rest = val[end + 1..].trim_start();
+    }
+    Ok(element)
+}
+
+fn unescape(text: &str) -> String {
+    let mut result = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+        let entity = &rest[1..end];
+        let ch = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match ch {
+            Some(ch) => {
+                result.push(ch);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                result.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_netlist() {
+        let text = r#"<?xml version="1.0" encoding="utf-8"?>
+<!-- KiCad netlist -->
+<export version="E">
+  <components>
+    <comp ref="C1">
+      <value>100n</value>
+      <fields><field name="MPN">GRM155R71C104KA88D</field></fields>
+      <property name="dnp"/>
+    </comp>
+    <comp ref='D1'><value>LED &amp; 1k</value></comp>
+  </components>
+</export>"#;
+        let export = Element::parse(text).unwrap();
+        assert_eq!(export.name(), "export");
+        let comps: Vec<&Element> = export
+            .child("components")
+            .unwrap()
+            .children("comp")
+            .collect();
+        assert_eq!(comps.len(), 2);
+        assert_eq!(comps[0].attr("ref"), Some("C1"));
+        assert_eq!(comps[0].child("value").unwrap().text(), "100n");
+        let field = comps[0].child("fields").unwrap().child("field").unwrap();
+        assert_eq!(field.attr("name"), Some("MPN"));
+        assert_eq!(field.text(), "GRM155R71C104KA88D");
+        assert!(comps[0].child("property").is_some());
+        assert_eq!(comps[1].child("value").unwrap().text(), "LED & 1k");
+    }
+
+    #[test]
+    fn quoted_attribute_with_angle_bracket() {
+        let export =
+            Element::parse(r#"<export><property name="Rating" value=">5V"/></export>"#).unwrap();
+        let property = export.child("property").unwrap();
+        assert_eq!(property.attr("value"), Some(">5V"));
+        assert_eq!(property.attr("name"), Some("Rating"));
+    }
+
+    #[test]
+    fn reject_mismatched_tags() {
+        assert!(Element::parse("<export><components></export>").is_err());
+        assert!(Element::parse("<export value=\"1>").is_err());
+    }
+}